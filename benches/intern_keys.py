@@ -0,0 +1,37 @@
+"""Memory kept by `loads` results with and without `intern_keys`.
+
+Run with `python benches/intern_keys.py` after building the extension.
+"""
+
+import tracemalloc
+
+from stc import stc_rust
+
+
+def repeated_keys(items: int) -> str:
+    """`items` list entries that all use the same handful of keys."""
+    lines = []
+    for i in range(items):
+        lines += [f"jobs.${i}.status: {i % 3}", f"jobs.${i}.priority: {i % 5}", f"jobs.${i}.retries: 0"]
+    return "\n".join(lines)
+
+
+def retained_bytes(doc: str, **kwargs) -> int:
+    tracemalloc.start()
+    result = stc_rust.loads(doc, **kwargs)
+    size, _ = tracemalloc.get_traced_memory()
+    tracemalloc.stop()
+    del result
+    return size
+
+
+def main() -> None:
+    doc = repeated_keys(10_000)
+    plain = retained_bytes(doc)
+    interned = retained_bytes(doc, intern_keys=True)
+    print(f"{'repeated_keys_10k':<32} {plain:>12,} B")
+    print(f"{'repeated_keys_10k intern_keys':<32} {interned:>12,} B  ({interned / plain:.0%})")
+
+
+if __name__ == "__main__":
+    main()
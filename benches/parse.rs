@@ -0,0 +1,51 @@
+//! Parser timings. Run with `cargo bench`.
+//!
+//! Uses a plain `Instant` loop rather than a benchmarking framework so it
+//! builds with the crate's existing dependencies.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use stc_rust::parse_stc;
+
+fn bench(name: &str, input: &str, iters: u32) -> Duration {
+    // warm up once so the first iteration doesn't pay for page faults
+    black_box(parse_stc(input).unwrap());
+    let start = Instant::now();
+    for _ in 0..iters {
+        black_box(parse_stc(black_box(input)).unwrap());
+    }
+    let per_iter = start.elapsed() / iters;
+    println!("{name:<32} {:>10.3} ms/iter", per_iter.as_secs_f64() * 1e3);
+    per_iter
+}
+
+/// Fail if 4x the input took far more than 4x as long, i.e. parsing has
+/// gone quadratic. The bound is loose so timing noise does not trip it.
+fn assert_linear(name: &str, small: Duration, large: Duration) {
+    let ratio = large.as_secs_f64() / small.as_secs_f64();
+    assert!(ratio < 10.0, "{name}: 4x the input took {ratio:.1}x as long");
+}
+
+/// `lines` lines of nested keys, the shape of a large generated config.
+fn nested_keys(lines: usize) -> String {
+    (0..lines)
+        .map(|i| format!("services.${}.config.section_{}.value: {i}", i / 10, i % 10))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// One string block of `lines` content lines, e.g. an embedded log file.
+fn string_block(lines: usize) -> String {
+    let body: Vec<String> = (0..lines).map(|i| format!("line {i}: some ``` text")).collect();
+    format!("log: ````\n{}\n````", body.join("\n"))
+}
+
+fn main() {
+    let small = bench("nested_keys_10k", &nested_keys(10_000), 20);
+    let large = bench("nested_keys_40k", &nested_keys(40_000), 5);
+    assert_linear("nested_keys", small, large);
+    let small = bench("string_block_100k", &string_block(100_000), 20);
+    let large = bench("string_block_400k", &string_block(400_000), 5);
+    assert_linear("string_block", small, large);
+}
@@ -42,3 +42,9 @@ def make_fence():
         return f"{key}: {ticks}\n{s}\n{ticks}"
 
     return _mk
+
+
+@pytest.fixture(scope="session")
+def dumps_fn():
+    from stc import dumps
+    return dumps
@@ -1 +1,9 @@
-from .loading import load, loads
\ No newline at end of file
+from .loading import load, load_async, loads, loads_all, loads_into, try_loads
+from .dumping import dump, dumps, min_fence
+from .linting import lint, validate
+from .query import empty_type_hints, iter_items, merge, parse_with_spans, set_at, values_of_type
+
+try:
+    from stc.stc_rust import StcReader
+except ImportError:
+    pass
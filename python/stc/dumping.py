@@ -0,0 +1,63 @@
+import os
+from typing import Any, TextIO, Union
+
+try:
+    from stc.stc_rust import dump as rust_dump, dumps as rust_dumps, min_fence as rust_min_fence
+except ImportError:
+    rust_dump = rust_dumps = rust_min_fence = None
+
+
+def min_fence(s: str) -> int:
+    """
+    Returns how many backticks are needed to fence `s` as a string block.
+
+    This is one more than the longest run of backticks in `s`, and at least 3,
+    the rule `dumps` uses.
+
+    Args:
+        s (str): The block content.
+
+    Returns:
+        int: The fence length.
+    """
+    if rust_min_fence is None:
+        raise RuntimeError("`min_fence` requires the Rust implementation (`stc_rust` not installed).")
+    return rust_min_fence(s)
+
+
+def dumps(obj: Any, **options: Any) -> str:
+    """
+    Serializes a dictionary into a string of STC.
+
+    Args:
+        obj (Any): A dict whose values are dicts, lists, str, int, float or bool.
+        **options: Keyword options forwarded to the Rust implementation, e.g.
+            `sort_keys=True` to emit dict keys in sorted order.
+
+    Returns:
+        str: The STC text.
+
+    Raises:
+        ValueError: If the object contains a value STC cannot represent.
+    """
+    if rust_dumps is None:
+        raise RuntimeError("`dumps` requires the Rust implementation (`stc_rust` not installed).")
+    return rust_dumps(obj, **options)
+
+
+def dump(obj: Any, path: Union[str, os.PathLike, TextIO], compress: bool = False, **options: Any) -> None:
+    """
+    Serializes `obj` as STC and writes it to `path`.
+
+    Args:
+        obj (Any): The value to serialize, as accepted by `dumps`.
+        path (str | os.PathLike | TextIO): Destination file, or a text file-like
+            object whose `write` is called as lines are produced. By convention
+            compressed files use the `.stc.gz` extension.
+        compress (bool): Write gzip-compressed output; `load` detects and
+            decompresses it automatically. Only supported for paths.
+        **options: Keyword options forwarded to `dumps`.
+    """
+    if rust_dump is None:
+        raise RuntimeError("`dump` requires the Rust implementation (`stc_rust` not installed).")
+    return rust_dump(obj, path, compress=compress, **options)
@@ -1,3 +1,10 @@
-class STCParseError(Exception):
-    """Custom exception for STC parsing errors."""
-    pass
\ No newline at end of file
+try:
+    # Share the native exception so errors from either implementation can be
+    # caught with the same class.
+    from stc.stc_rust import STCParseError
+except ImportError:
+    class STCParseError(Exception):
+        """Custom exception for STC parsing errors."""
+        lineno = None
+        colno = None
+        kind = None
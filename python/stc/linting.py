@@ -0,0 +1,37 @@
+try:
+    from stc.stc_rust import lint as rust_lint, validate as rust_validate
+except ImportError:
+    rust_lint = rust_validate = None
+
+
+def lint(stc_str: str) -> list[str]:
+    """
+    Parses a string of STC and returns warnings about suspicious keys.
+
+    Args:
+        stc_str (str): A string of STC configs.
+
+    Returns:
+        list[str]: One message per likely-duplicate key pair, e.g. `Server` vs `server`.
+
+    Raises:
+        STCParseError: If the input string is not valid.
+    """
+    if rust_lint is None:
+        raise RuntimeError("`lint` requires the Rust implementation (`stc_rust` not installed).")
+    return rust_lint(stc_str)
+
+
+def validate(stc_str: str) -> None:
+    """
+    Checks that a string is valid STC without building the parsed data.
+
+    Args:
+        stc_str (str): A string of STC configs.
+
+    Raises:
+        STCParseError: On the first problem found.
+    """
+    if rust_validate is None:
+        raise RuntimeError("`validate` requires the Rust implementation (`stc_rust` not installed).")
+    rust_validate(stc_str)
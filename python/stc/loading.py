@@ -1,13 +1,23 @@
+import asyncio
+import errno
 import logging
+import os
+from pathlib import Path
 
 from .exceptions import STCParseError
 from enum import Enum
-from typing import Any, Literal, TextIO
+from typing import Any, Literal, TextIO, Union
 
 try:
-    from stc.stc_rust import loads as rust_loads
+    from stc.stc_rust import (
+        gunzip as rust_gunzip,
+        loads as rust_loads,
+        loads_all as rust_loads_all,
+        loads_into as rust_loads_into,
+        try_loads as rust_try_loads,
+    )
 except ImportError:
-    rust_loads = None
+    rust_gunzip = rust_loads = rust_loads_all = rust_loads_into = rust_try_loads = None
 
 
 logger = logging.getLogger(__name__)
@@ -157,12 +167,15 @@ def finalize_dict(d: dict, prefix: str) -> dict:
     return d
 
 
-def loads(stc_str: str, impl: Literal['rust', 'python'] = 'rust') -> dict:
+def loads(stc_str: str, impl: Literal['rust', 'python'] = 'rust', **options: Any) -> dict:
     """
     Parses a string of STC and returns it as a dictionary.
     
     Args:
         stc_str (str): A string of STC configs.
+        **options: Keyword options forwarded to the Rust implementation, e.g.
+            `readonly=True` to get read-only views that suggest the closest
+            key on a failed lookup.
         
     Returns:
         dict: The parsed data as a dictionary.
@@ -172,12 +185,15 @@ def loads(stc_str: str, impl: Literal['rust', 'python'] = 'rust') -> dict:
     """
     if impl == 'rust':
         if rust_loads is not None:
-            return rust_loads(stc_str)
+            return rust_loads(stc_str, **options)
         else:
             logger.warning("Rust implementation not available (`stc_rust` not installed), falling back to Python implementation.")
-    if stc_str.strip() == "{}":
-        return {}
+    if options:
+        raise ValueError(f"Options {sorted(options)} are only supported by the Rust implementation.")
     lines = stc_str.split("\n")
+    # blank and `#` comment lines may surround the empty document `{}`
+    if [line.strip() for line in lines if line.strip() and not line.lstrip().startswith("#")] == ["{}"]:
+        return {}
     in_a_string = False
     string_value = ""
     bt_count = -1
@@ -185,7 +201,7 @@ def loads(stc_str: str, impl: Literal['rust', 'python'] = 'rust') -> dict:
     parsed = {}
     for line_num, line in enumerate(lines):
         if not in_a_string:
-            if not line.strip():
+            if not line.strip() or line.lstrip().startswith("#"):
                 continue
             if ":" not in line:
                 raise STCParseError(f"Line {line_num + 1} missing `:`. Line content:\n {line}")
@@ -218,17 +234,165 @@ def loads(stc_str: str, impl: Literal['rust', 'python'] = 'rust') -> dict:
     return finalize_dict(parsed, "")
 
 
-def load(fp: TextIO) -> Any:
+def loads_into(stc_str: str, target: dict, **options: Any) -> None:
+    """
+    Parses a string of STC into an existing dict, for reusing one dict in a
+    tight loop instead of allocating a new one per document.
+
+    Args:
+        stc_str (str): A string of STC configs whose top level is a dict.
+        target (dict): The dict to fill. It is cleared first, unless the
+            input does not parse, in which case it is left untouched.
+        **options: Keyword options accepted by `loads`, except `readonly`,
+            `dict_class` and `return_warnings`.
+
+    Raises:
+        STCParseError: If the input string is not valid.
+        ValueError: If the document's top level is a list.
+    """
+    if rust_loads_into is None:
+        raise RuntimeError("`loads_into` requires the Rust implementation (`stc_rust` not installed).")
+    rust_loads_into(stc_str, target, **options)
+
+
+def try_loads(stc_str: str, **options: Any) -> tuple[Any | None, str | None]:
+    """
+    Parses a string of STC like `loads`, but reports invalid input as a value
+    instead of raising, for batch validation or hot paths.
+
+    Args:
+        stc_str (str): A string of STC configs.
+        **options: Keyword options accepted by `loads`.
+
+    Returns:
+        tuple: `(value, None)` if the input parses, otherwise `(None, message)`
+            with the message `STCParseError` would carry.
+    """
+    if rust_try_loads is not None:
+        return rust_try_loads(stc_str, **options)
+    try:
+        return loads(stc_str, **options), None
+    except STCParseError as e:
+        return None, str(e)
+
+
+GZIP_MAGIC = b"\x1f\x8b"
+
+
+def loads_all(stc_str: str, **options: Any) -> list:
     """
-    Parse a structure from a file-like object containing your DSL.
+    Parses a stream of STC documents separated by lines that are exactly `---`.
 
     Args:
-        fp: Any text-mode file-like object (must support `.read()` returning str).
+        stc_str (str): The documents, one after another.
+        **options: Keyword options of `loads`, applied to every document.
+            `return_warnings` and `cache` are not supported.
+
+    Returns:
+        list: One parsed dictionary per document.
+
+    Raises:
+        STCParseError: If any document is invalid, or a separator appears
+            inside a string block.
+    """
+    if rust_loads_all is None:
+        raise RuntimeError("`loads_all` requires the Rust implementation (`stc_rust` not installed).")
+    return rust_loads_all(stc_str, **options)
+
+
+def _normalize_path(path: Union[str, os.PathLike]) -> Path:
+    """
+    Turns a `str` or `os.PathLike` into a `Path`, accepting either `/` or `\\`
+    as the separator. On POSIX a backslash is a valid file name character, so
+    it is only treated as a separator when the literal path does not exist.
+    """
+    raw = os.fspath(path)
+    if os.sep == "/" and "\\" in raw and not os.path.exists(raw):
+        raw = raw.replace("\\", "/")
+    return Path(os.path.normpath(raw))
+
+
+def read_stc_file(path: Union[str, os.PathLike]) -> str:
+    """
+    Reads an STC file, transparently decompressing gzip (`.stc.gz`) content.
+
+    Compression is detected from the gzip magic bytes, so the extension is
+    only a convention.
+
+    Raises:
+        FileNotFoundError: If `path` does not exist.
+        IsADirectoryError: If `path` is a directory.
+        UnicodeDecodeError: If the (decompressed) content is not UTF-8; the
+            message gives the byte offset of the first invalid sequence.
+    """
+    path = _normalize_path(path)
+    if path.is_dir():
+        raise IsADirectoryError(errno.EISDIR, "Is a directory, not an STC file", str(path))
+    if not path.exists():
+        raise FileNotFoundError(errno.ENOENT, "No such STC file", str(path))
+    data = path.read_bytes()
+    if data[:2] == GZIP_MAGIC:
+        if rust_gunzip is None:
+            raise RuntimeError("Reading a gzip file requires the Rust implementation (`stc_rust` not installed).")
+        data = rust_gunzip(data)
+    try:
+        return data.decode("utf-8")
+    except UnicodeDecodeError as e:
+        raise UnicodeDecodeError(
+            e.encoding, e.object, e.start, e.end,
+            f"{path} is not valid UTF-8: invalid sequence at byte offset {e.start}",
+        ) from None
+
+
+def load(fp: Union[TextIO, str, os.PathLike], **options: Any) -> Any:
+    """
+    Parse a structure from a file-like object or path containing your DSL.
+
+    Args:
+        fp: Any text-mode file-like object (must support `.read()` returning str),
+            or a path to a plain or gzip-compressed STC file.
+        **options: Keyword options forwarded to `loads`. With
+            `allow_includes=True`, `key: !include other.stc` values in a
+            file at a path are replaced by the parsed content of
+            `other.stc`, relative to the including file. Included files are
+            read like the file itself, so they may be gzip-compressed too.
 
     Returns:
         The parsed Python object.
 
     Raises:
+        OSError / UnicodeDecodeError: If a path cannot be read as UTF-8 text;
+            see `read_stc_file`. These are never `STCParseError`.
         Whatever exceptions `loads` may raise if the input is invalid.
     """
-    return loads(fp.read())
\ No newline at end of file
+    if isinstance(fp, (str, os.PathLike)):
+        if options.get("allow_includes"):
+            # `!include` paths resolve relative to the file, so Rust parses it,
+            # reading each file through `read_stc_file`
+            from stc.stc_rust import load_file
+            return load_file(_normalize_path(fp), read=read_stc_file, **options)
+        return loads(read_stc_file(fp), **options)
+    return loads(fp.read(), **options)
+
+
+async def load_async(fp: Union[TextIO, str, os.PathLike], **options: Any) -> Any:
+    """
+    Like `load`, but reads the file on a worker thread so the event loop is
+    not blocked on I/O. Parsing still happens on the calling thread, except
+    with `allow_includes=True`, where included files are read as the file is
+    parsed, so both happen on the worker thread.
+
+    Args:
+        fp: A path or text-mode file-like object, as for `load`.
+        **options: Keyword options forwarded to `loads`.
+
+    Returns:
+        The parsed Python object.
+    """
+    if isinstance(fp, (str, os.PathLike)):
+        if options.get("allow_includes"):
+            return await asyncio.to_thread(load, fp, **options)
+        text = await asyncio.to_thread(read_stc_file, fp)
+    else:
+        text = await asyncio.to_thread(fp.read)
+    return loads(text, **options)
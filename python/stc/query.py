@@ -0,0 +1,106 @@
+from typing import Any
+
+try:
+    from stc import stc_rust
+except ImportError:
+    stc_rust = None
+
+
+def _rust():
+    if stc_rust is None:
+        raise RuntimeError("This helper requires the Rust implementation (`stc_rust` not installed).")
+    return stc_rust
+
+
+def values_of_type(stc_str: str, kind: str) -> list[tuple[str, Any]]:
+    """
+    Parses a string of STC and collects every leaf value of one type.
+
+    Args:
+        stc_str (str): A string of STC configs.
+        kind (str): One of "string", "int", "float", "bool", "bytes", or
+            "list"/"dict" for empty collections.
+
+    Returns:
+        list[tuple[str, Any]]: `(path, value)` pairs with dict keys in sorted
+            order and list items in index order, like the dicts `loads`
+            returns, with paths written as STC keys such as `servers.$0.host`.
+    """
+    return _rust().values_of_type(stc_str, kind)
+
+
+def empty_type_hints(stc_str: str, **options: Any) -> list[tuple[str, str]]:
+    """
+    Parses a string of STC and lists its typed empty collections.
+
+    Args:
+        stc_str (str): A string of STC configs.
+        **options: Keyword options accepted by `loads`.
+
+    Returns:
+        list[tuple[str, str]]: `(path, element type)` pairs in document order,
+            e.g. `("ports", "int")` for `ports: []int`. The parsed document
+            holds a plain `[]` or `{}` there.
+    """
+    return _rust().empty_type_hints(stc_str, **options)
+
+
+def parse_with_spans(stc_str: str, **options: Any) -> tuple[Any, dict[str, tuple[int, int, int, int]]]:
+    """
+    Parses a string of STC and reports where each value is written.
+
+    Args:
+        stc_str (str): A string of STC configs.
+        **options: Keyword options accepted by `loads`.
+
+    Returns:
+        tuple: The parsed document and a dict from the JSON pointer of each
+            value (e.g. `"/servers/0/host"`) to its `(line, col, end_line,
+            end_col)` in the source, counted from 1 with `end_col` just past
+            the value. Dicts and lists assembled from several lines have no
+            entry of their own.
+    """
+    return _rust().parse_with_spans(stc_str, **options)
+
+
+def iter_items(obj: Any) -> list[tuple[str, Any]]:
+    """
+    Flattens a parsed STC dict or list back into its leaf key paths.
+
+    Args:
+        obj (Any): The result of `loads`, or any nested dict/list.
+
+    Returns:
+        list[tuple[str, Any]]: `(path, value)` pairs in iteration order, with
+            paths written as STC keys such as `servers.$0.host`. Empty dicts
+            and lists are leaves too.
+    """
+    return _rust().iter_items(obj)
+
+
+def set_at(obj: Any, path: str, value: Any) -> None:
+    """
+    Sets one value of a parsed STC dict or list in place.
+
+    Args:
+        obj (Any): The result of `loads`, or any nested dict/list.
+        path (str): An STC key path such as `servers.$0.port`. Missing dicts
+            and lists along the way are created, and `$` (or `$N` one past the
+            end) appends to a list.
+        value (Any): The new value.
+
+    Raises:
+        STCParseError: If the path is malformed, indexes a dict with `$N` or
+            names a key of a list, or is out of range.
+    """
+    _rust().set_at(obj, path, value)
+
+
+def merge(base: dict, overlay: dict, *, concat_arrays: bool = False) -> dict:
+    """
+    Deep-merges two parsed STC documents, returning a new dict.
+
+    Nested dicts merge key by key and other overlay values replace the base
+    value. Lists are replaced wholesale unless `concat_arrays` is set.
+    """
+    return _rust().merge(base, overlay, concat_arrays=concat_arrays)
@@ -0,0 +1,64 @@
+"""Type stubs for the `stc.stc_rust` extension module."""
+
+import os
+from typing import Any, Callable, Iterator, Literal, Mapping, Optional, Union
+
+__version__: str
+
+class STCParseError(Exception):
+    lineno: Optional[int]
+    colno: Optional[int]
+    kind: Optional[str]
+
+class StcView(Mapping[str, Any]):
+    def __getitem__(self, key: str) -> Any: ...
+    def __len__(self) -> int: ...
+    def __iter__(self) -> Iterator[str]: ...
+
+class StcReader:
+    def __init__(self, path: Union[str, os.PathLike]) -> None: ...
+    def __enter__(self) -> "StcReader": ...
+    def __exit__(self, *args: Any) -> bool: ...
+    def __iter__(self) -> "StcReader": ...
+    def __next__(self) -> tuple[str, Any]: ...
+
+def loads(s: str, **options: Any) -> Any: ...
+def parse(s: str, **options: Any) -> Any: ...
+def try_loads(s: str, **options: Any) -> tuple[Optional[Any], Optional[str]]: ...
+def loads_into(s: str, target: dict[str, Any], **options: Any) -> None: ...
+def load_file(
+    path: Union[str, os.PathLike],
+    *,
+    read: Optional[Callable[[os.PathLike], str]] = None,
+    **options: Any,
+) -> Any: ...
+def loads_all(s: str, **kwargs: Any) -> list[Any]: ...
+def loads_json_str(s: str, **options: Any) -> str: ...
+def to_json(s: str, indent: Optional[int] = 2, *, keep_float_text: bool = False, **options: Any) -> str: ...
+def canonical_json(s: str) -> str: ...
+def to_jsonl(s: str, fp: Optional[Any] = None, **options: Any) -> Optional[str]: ...
+def to_toml(s: str, **options: Any) -> str: ...
+def parse_scalar(raw: str) -> Any: ...
+def empty_type_hints(s: str, **options: Any) -> list[tuple[str, str]]: ...
+def parse_with_spans(s: str, **options: Any) -> tuple[Any, dict[str, tuple[int, int, int, int]]]: ...
+def dumps(obj: Any, *, sort_keys: bool = False, collapse_integral_floats: bool = False) -> str: ...
+def dump(
+    obj: Any,
+    fp: Any,
+    *,
+    sort_keys: bool = False,
+    collapse_integral_floats: bool = False,
+    compress: bool = False,
+) -> None: ...
+def min_fence(s: str) -> int: ...
+def gunzip(data: bytes) -> bytes: ...
+def lint(s: str) -> list[str]: ...
+def validate(s: str) -> None: ...
+def values_of_type(
+    s: str, kind: Literal["string", "int", "float", "bool", "bytes", "list", "dict"]
+) -> list[tuple[str, Any]]: ...
+def iter_items(obj: Any) -> list[tuple[str, Any]]: ...
+def set_at(obj: Any, path: str, value: Any) -> None: ...
+def merge(base: dict, overlay: dict, *, concat_arrays: bool = False) -> dict: ...
+def clear_cache() -> None: ...
+def cache_info() -> dict[str, int]: ...
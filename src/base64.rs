@@ -0,0 +1,81 @@
+//! Base64 blocks, `` key: b64``` ``, holding binary data.
+//!
+//! The block content is standard base64 (RFC 4648, with padding); whitespace
+//! and line breaks inside it are ignored. JSON has no bytes type, so the data
+//! is carried through the `Value` tree as a one-entry object
+//! `{BYTES_KEY: "<base64>"}`, like a number lexeme. Python sees `bytes`;
+//! `to_json` and `to_toml` write the base64 text as a string.
+
+use serde_json::{Map, Value};
+
+/// Reserved object key marking base64 data.
+pub const BYTES_KEY: &str = "\u{0}bytes";
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub(crate) fn encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = u32::from(b[0]) << 16 | u32::from(b[1]) << 8 | u32::from(b[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Decode base64 text, ignoring whitespace. `None` if it is malformed.
+pub(crate) fn decode(text: &str) -> Option<Vec<u8>> {
+    let digits: Vec<u8> = text.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+    if !digits.len().is_multiple_of(4) {
+        return None;
+    }
+    let mut out = Vec::with_capacity(digits.len() / 4 * 3);
+    for (i, quad) in digits.chunks(4).enumerate() {
+        let last = (i + 1) * 4 == digits.len();
+        let pad = quad.iter().rev().take_while(|&&b| b == b'=').count();
+        if pad > 2 || (pad > 0 && !last) {
+            return None;
+        }
+        let mut n = 0u32;
+        for &b in &quad[..4 - pad] {
+            n = n << 6 | ALPHABET.iter().position(|&a| a == b)? as u32;
+        }
+        n <<= 6 * pad as u32;
+        out.extend_from_slice(&n.to_be_bytes()[1..4 - pad]);
+    }
+    Some(out)
+}
+
+pub(crate) fn marker(data: &[u8]) -> Value {
+    let mut m = Map::new();
+    m.insert(BYTES_KEY.to_string(), Value::String(encode(data)));
+    Value::Object(m)
+}
+
+/// The base64 text if `v` is a bytes marker.
+pub(crate) fn bytes_of(v: &Value) -> Option<&str> {
+    match v {
+        Value::Object(m) if m.len() == 1 => m.get(BYTES_KEY)?.as_str(),
+        _ => None,
+    }
+}
+
+/// Replace every bytes marker under `v` with its base64 string, for formats
+/// without a bytes type.
+pub(crate) fn markers_to_strings(v: &mut Value) {
+    if let Some(text) = bytes_of(v) {
+        *v = Value::String(text.to_string());
+        return;
+    }
+    match v {
+        Value::Array(arr) => arr.iter_mut().for_each(markers_to_strings),
+        Value::Object(obj) => obj.values_mut().for_each(markers_to_strings),
+        _ => {}
+    }
+}
@@ -0,0 +1,119 @@
+//! Building a document in code, one key path at a time, without writing STC
+//! text and parsing it back.
+
+use serde_json::Value;
+
+use crate::{base64, err, key_segment, lexeme, parse_key, EmptyObject, ErrorKind, Node, ParseOptions, StcError, StcParser};
+
+/// Builds a document from `(key path, value)` assignments, with the same rules
+/// as parsing one line per `set` call: `$N` and `$` list indices, duplicate key
+/// policy, and list checks when the document is built. Errors name the n-th
+/// `set` call as line n.
+///
+/// ```
+/// use serde_json::json;
+///
+/// let mut b = stc_rust::DocumentBuilder::new();
+/// b.set("servers.$.host", json!("a"))?.set("servers.$.host", json!("b"))?;
+/// b.set("debug", json!(true))?;
+/// assert_eq!(b.build()?, json!({"debug": true, "servers": [{"host": "a"}, {"host": "b"}]}));
+/// # Ok::<(), stc_rust::StcError>(())
+/// ```
+pub struct DocumentBuilder {
+    parser: StcParser,
+}
+
+impl Default for DocumentBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DocumentBuilder {
+    pub fn new() -> Self {
+        Self::with_options(ParseOptions::default())
+    }
+
+    /// A builder applying `opts`; only the options that affect assignments
+    /// (such as `duplicate_keys` and `max_list_len`) matter here.
+    pub fn with_options(opts: ParseOptions) -> Self {
+        DocumentBuilder { parser: StcParser::with_options(opts) }
+    }
+
+    /// Set `value` at the STC key `path`, e.g. `items.$0.name`. Objects and
+    /// arrays are spread into one assignment per leaf, so later calls can add
+    /// to them.
+    pub fn set(&mut self, path: &str, value: Value) -> Result<&mut Self, StcError> {
+        self.parser.ln += 1;
+        let ln = self.parser.ln;
+        let segments = parse_key(path, Some(ln))?;
+        let path = self.parser.resolve_auto_indices(&segments, ln)?;
+        self.set_value(path, value, ln)?;
+        Ok(self)
+    }
+
+    fn set_value(&mut self, mut path: Vec<String>, value: Value, ln: usize) -> Result<(), StcError> {
+        // a lexeme marker sets the number it stands for, as a parse would
+        if let Some(n) = lexeme::lexeme_of(&value).and_then(|raw| lexeme::lexeme_node(raw, &self.parser.opts)) {
+            return self.parser.fill(&path, n, ln);
+        }
+        if let Some(data) = base64::bytes_of(&value).and_then(base64::decode) {
+            return self.parser.fill(&path, Node::Bytes(data), ln);
+        }
+        let node = match value {
+            Value::Null => {
+                return Err(err(
+                    ErrorKind::InvalidValue,
+                    format!("Key `{}` is null, which STC cannot represent.", path.join(".")),
+                    Some(ln),
+                ));
+            }
+            Value::Bool(b) => Node::Bool(b),
+            Value::Number(n) => match (n.as_i64(), n.as_f64()) {
+                (Some(i), _) => Node::Int(i),
+                (None, Some(f)) if n.is_f64() => Node::Float(f),
+                _ => {
+                    return Err(err(
+                        ErrorKind::InvalidValue,
+                        format!("Key `{}` has an integer {n} that does not fit in i64.", path.join(".")),
+                        Some(ln),
+                    ));
+                }
+            },
+            Value::String(s) => Node::Str(s),
+            Value::Object(obj) if obj.is_empty() => Node::Empty(EmptyObject::EmptyDict),
+            Value::Array(arr) if arr.is_empty() => Node::Empty(EmptyObject::EmptyList),
+            Value::Object(obj) => {
+                for (k, v) in obj {
+                    if k.contains('\0') {
+                        return Err(err(
+                            ErrorKind::InvalidKey,
+                            format!("Invalid key: {} under `{}`. Key segments cannot contain NUL.", k.escape_debug(), path.join(".")),
+                            Some(ln),
+                        ));
+                    }
+                    path.push(key_segment(&k).into_owned());
+                    self.set_value(path.clone(), v, ln)?;
+                    path.pop();
+                }
+                return Ok(());
+            }
+            Value::Array(arr) => {
+                // spread items use explicit indices, so the list cannot also take `$`
+                self.parser.explicit_index.insert(path.join("."));
+                for (i, v) in arr.into_iter().enumerate() {
+                    path.push(format!("${i}"));
+                    self.set_value(path.clone(), v, ln)?;
+                    path.pop();
+                }
+                return Ok(());
+            }
+        };
+        self.parser.fill(&path, node, ln)
+    }
+
+    /// Check the assembled lists and dicts and return the document.
+    pub fn build(self) -> Result<Value, StcError> {
+        self.parser.finish()
+    }
+}
@@ -0,0 +1,94 @@
+//! Process-wide LRU cache of parse results, used by `loads(s, cache=True)`.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use serde_json::Value;
+
+use crate::{parse_stc_raw, ParseOptions, StcError};
+
+const CAPACITY: usize = 128;
+
+struct Entry {
+    input: String,
+    opts: String,
+    value: Value,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct ParseCache {
+    entries: HashMap<u64, Entry>,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+}
+
+static CACHE: Mutex<Option<ParseCache>> = Mutex::new(None);
+
+fn with_cache<R>(f: impl FnOnce(&mut ParseCache) -> R) -> R {
+    let mut guard = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    f(guard.get_or_insert_with(ParseCache::default))
+}
+
+/// Parse through the cache. Options are part of the key, since they change
+/// the result; the input is compared on a hit so hash collisions can't leak.
+pub(crate) fn parse_cached(input: &str, opts: &ParseOptions) -> Result<Value, StcError> {
+    let opts_key = format!("{opts:?}");
+    let mut hasher = DefaultHasher::new();
+    input.hash(&mut hasher);
+    opts_key.hash(&mut hasher);
+    let key = hasher.finish();
+
+    let hit = with_cache(|c| {
+        c.tick += 1;
+        let tick = c.tick;
+        match c.entries.get_mut(&key) {
+            Some(e) if e.input == input && e.opts == opts_key => {
+                e.last_used = tick;
+                c.hits += 1;
+                Some(e.value.clone())
+            }
+            _ => {
+                c.misses += 1;
+                None
+            }
+        }
+    });
+    if let Some(v) = hit {
+        return Ok(v);
+    }
+
+    // Parse outside the lock so a large document doesn't block other callers.
+    let value = parse_stc_raw(input, opts)?;
+    with_cache(|c| {
+        let full = c.entries.len() >= CAPACITY && !c.entries.contains_key(&key);
+        if let Some(oldest) = c.entries.iter().filter(|_| full).min_by_key(|(_, e)| e.last_used).map(|(k, _)| *k) {
+            c.entries.remove(&oldest);
+        }
+        let entry = Entry { input: input.to_string(), opts: opts_key, value: value.clone(), last_used: c.tick };
+        c.entries.insert(key, entry);
+    });
+    Ok(value)
+}
+
+/// Drop every cached parse result and reset the hit/miss counters.
+#[pyfunction]
+pub(crate) fn clear_cache() {
+    with_cache(|c| *c = ParseCache::default());
+}
+
+/// Counters for the parse cache: `hits`, `misses` and current `size`.
+#[pyfunction]
+pub(crate) fn cache_info(py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+    let (hits, misses, size) = with_cache(|c| (c.hits, c.misses, c.entries.len()));
+    let info = PyDict::new(py);
+    info.set_item("hits", hits)?;
+    info.set_item("misses", misses)?;
+    info.set_item("size", size)?;
+    Ok(info)
+}
@@ -0,0 +1,423 @@
+//! Serialization of values back into STC text.
+//!
+//! Both serde `Value`s and Python objects are walked into the same `Emitter`,
+//! so the two front-ends always agree on key paths, fences and number formats.
+
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3::types::{PyBool, PyBytes, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple};
+use serde_json::Value;
+
+use crate::{base64, gzip, is_identifier, key_segment, lexeme, StcError};
+
+/// Options controlling how values are written out as STC.
+#[derive(Debug, Clone, Default)]
+pub struct DumpOptions {
+    /// Emit dict keys in sorted order instead of the source mapping's order.
+    pub sort_keys: bool,
+    /// Write floats with no fractional part (e.g. `5.0`) as integers.
+    pub collapse_integral_floats: bool,
+}
+
+fn ser_err<S: Into<String>>(s: S) -> StcError {
+    StcError::Serialize(s.into())
+}
+
+/// Smallest number of backticks that can fence `s`: one more than the longest
+/// run of backticks anywhere in `s`, and never fewer than three.
+///
+/// Only a line consisting of exactly the fence closes a block, so this is
+/// conservative, but it is always safe and easy to reason about.
+pub fn min_fence_len(s: &str) -> usize {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in s.chars() {
+        if c == '`' {
+            run += 1;
+            longest = longest.max(run);
+        } else {
+            run = 0;
+        }
+    }
+    (longest + 1).max(3)
+}
+
+/// Receives output as it is produced when an `Emitter` streams instead of
+/// accumulating.
+#[cfg(feature = "python")]
+type Sink<'s> = &'s mut dyn FnMut(&str) -> PyResult<()>;
+
+pub(crate) struct Emitter<'o> {
+    opts: &'o DumpOptions,
+    out: String,
+    started: bool,
+    #[cfg(feature = "python")]
+    sink: Option<Sink<'o>>,
+}
+
+impl<'o> Emitter<'o> {
+    pub(crate) fn new(opts: &'o DumpOptions) -> Self {
+        Emitter {
+            opts,
+            out: String::new(),
+            started: false,
+            #[cfg(feature = "python")]
+            sink: None,
+        }
+    }
+
+    /// An emitter that hands each finished value's lines to `sink` from the
+    /// `pyobj` walker instead of keeping them.
+    #[cfg(feature = "python")]
+    pub(crate) fn streaming(opts: &'o DumpOptions, sink: Sink<'o>) -> Self {
+        Emitter { sink: Some(sink), ..Emitter::new(opts) }
+    }
+
+    pub(crate) fn finish(self) -> String {
+        self.out
+    }
+
+    fn line(&mut self, line: &str) {
+        if self.started {
+            self.out.push('\n');
+        }
+        self.started = true;
+        self.out.push_str(line);
+    }
+
+    #[cfg(feature = "python")]
+    fn flush(&mut self) -> PyResult<()> {
+        if let Some(sink) = self.sink.as_mut()
+            && !self.out.is_empty()
+        {
+            sink(&self.out)?;
+            self.out.clear();
+        }
+        Ok(())
+    }
+
+    /// Keys that are not identifiers are quoted, as in `"a.b"`.
+    fn child_path(prefix: &str, key: &str) -> Result<String, StcError> {
+        if key.contains('\0') {
+            return Err(ser_err(format!("Key {key:?} cannot be represented in STC. Keys cannot contain NUL.")));
+        }
+        let key = key_segment(key);
+        Ok(if prefix.is_empty() { key.into_owned() } else { format!("{prefix}.{key}") })
+    }
+
+    fn index_path(prefix: &str, idx: usize) -> String {
+        format!("{prefix}.${idx}")
+    }
+
+    fn root_must_be_dict(prefix: &str, is_dict: bool) -> Result<(), StcError> {
+        if prefix.is_empty() && !is_dict {
+            return Err(ser_err("The top-level value must be a dict."));
+        }
+        Ok(())
+    }
+
+    fn bool(&mut self, path: &str, b: bool) {
+        self.line(&format!("{path}: `{b}`"));
+    }
+
+    fn int(&mut self, path: &str, i: i64) {
+        self.line(&format!("{path}: {i}"));
+    }
+
+    fn float(&mut self, path: &str, f: f64) -> Result<(), StcError> {
+        if !f.is_finite() {
+            return Err(ser_err(format!("Key `{path}` has a non-finite float {f}, which STC cannot represent.")));
+        }
+        if self.opts.collapse_integral_floats && f.fract() == 0.0 && f.abs() < i64::MAX as f64 {
+            self.int(path, f as i64);
+            return Ok(());
+        }
+        // `{:?}` always keeps a `.` or exponent, so the value reads back as a float.
+        self.line(&format!("{path}: {f:?}"));
+        Ok(())
+    }
+
+    fn string(&mut self, path: &str, s: &str) {
+        let fence = "`".repeat(min_fence_len(s));
+        self.line(&format!("{path}: {fence}"));
+        // An empty string still needs one (blank) content line.
+        self.line(s);
+        self.line(&fence);
+    }
+
+    fn empty(&mut self, path: &str, is_list: bool) {
+        if path.is_empty() {
+            self.line("{}");
+        } else {
+            self.line(&format!("{path}: {}", if is_list { "[]" } else { "{}" }));
+        }
+    }
+
+    fn include(&mut self, path: &str, file: &str) {
+        self.line(&format!("{path}: !include {file}"));
+    }
+
+    fn null(&mut self, path: &str) -> Result<(), StcError> {
+        Err(ser_err(format!("Key `{path}` is null, which STC cannot represent.")))
+    }
+
+    /// Binary data as a base64 block. The alphabet has no backticks, so a
+    /// plain fence always works.
+    fn bytes(&mut self, path: &str, text: &str) {
+        self.line(&format!("{path}: b64```"));
+        for chunk in text.as_bytes().chunks(76) {
+            self.line(std::str::from_utf8(chunk).expect("base64 is ASCII"));
+        }
+        self.line("```");
+    }
+
+    /// A preserved number lexeme, written back exactly as it was read.
+    fn lexeme(&mut self, path: &str, raw: &str) {
+        self.line(&format!("{path}: {raw}"));
+    }
+
+    /// Walk a serde `Value`.
+    pub(crate) fn value(&mut self, path: &str, v: &Value) -> Result<(), StcError> {
+        Self::root_must_be_dict(path, v.is_object())?;
+        if let Some(raw) = lexeme::lexeme_of(v).filter(|_| !path.is_empty()) {
+            self.lexeme(path, raw);
+            return Ok(());
+        }
+        if let Some(text) = base64::bytes_of(v).filter(|_| !path.is_empty()) {
+            self.bytes(path, text);
+            return Ok(());
+        }
+        match v {
+            Value::Null => self.null(path)?,
+            Value::Bool(b) => self.bool(path, *b),
+            Value::Number(n) => {
+                if let Some(i) = n.as_i64() {
+                    self.int(path, i);
+                } else if let Some(f) = n.as_f64().filter(|_| n.is_f64()) {
+                    self.float(path, f)?;
+                } else {
+                    return Err(ser_err(format!("Key `{path}` has an integer {n} that does not fit in i64.")));
+                }
+            }
+            Value::String(s) => self.string(path, s),
+            Value::Array(arr) if arr.is_empty() => self.empty(path, true),
+            Value::Array(arr) => {
+                for (i, item) in arr.iter().enumerate() {
+                    self.value(&Self::index_path(path, i), item)?;
+                }
+            }
+            Value::Object(obj) if obj.is_empty() => self.empty(path, false),
+            Value::Object(obj) => {
+                let mut entries: Vec<(&String, &Value)> = obj.iter().collect();
+                if self.opts.sort_keys {
+                    entries.sort_by(|a, b| a.0.cmp(b.0));
+                }
+                for (k, child) in entries {
+                    self.value(&Self::child_path(path, k)?, child)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Walk a Python object, keeping each dict's own iteration order unless
+    /// `sort_keys` is set.
+    #[cfg(feature = "python")]
+    pub(crate) fn pyobj(&mut self, path: &str, obj: &Bound<'_, PyAny>) -> PyResult<()> {
+        Self::root_must_be_dict(path, obj.is_instance_of::<PyDict>())?;
+        if obj.is_none() {
+            self.null(path)?;
+        } else if let Some(raw) = lexeme::py_lexeme(obj)? {
+            self.lexeme(path, &raw);
+        } else if let Ok(b) = obj.downcast::<PyBool>() {
+            self.bool(path, b.is_true());
+        } else if obj.is_instance_of::<PyInt>() {
+            let i: i64 = obj.extract().map_err(|_| {
+                ser_err(format!("Key `{path}` has an integer {obj} that does not fit in i64."))
+            })?;
+            self.int(path, i);
+        } else if let Ok(f) = obj.downcast::<PyFloat>() {
+            self.float(path, f.value())?;
+        } else if let Ok(s) = obj.downcast::<PyString>() {
+            self.string(path, s.to_str()?);
+        } else if let Ok(b) = obj.downcast::<PyBytes>() {
+            self.bytes(path, &base64::encode(b.as_bytes()));
+        } else if let Ok(d) = obj.downcast::<PyDict>() {
+            if d.is_empty() {
+                self.empty(path, false);
+                return Ok(());
+            }
+            let mut entries = Vec::with_capacity(d.len());
+            for (k, v) in d.iter() {
+                let Ok(k) = k.downcast::<PyString>() else {
+                    return Err(ser_err(format!("Dict keys must be strings, found {} under `{path}`.", k.repr()?)).into());
+                };
+                entries.push((k.to_str()?.to_string(), v));
+            }
+            if self.opts.sort_keys {
+                entries.sort_by(|a, b| a.0.cmp(&b.0));
+            }
+            for (k, v) in entries {
+                self.pyobj(&Self::child_path(path, &k)?, &v)?;
+            }
+        } else if obj.is_instance_of::<PyList>() || obj.is_instance_of::<PyTuple>() {
+            let items: Vec<Bound<'_, PyAny>> = obj.try_iter()?.collect::<PyResult<_>>()?;
+            if items.is_empty() {
+                self.empty(path, true);
+            }
+            for (i, item) in items.iter().enumerate() {
+                self.pyobj(&Self::index_path(path, i), item)?;
+            }
+        } else {
+            return Err(ser_err(format!(
+                "Key `{path}` has a value of type {} that STC cannot represent.",
+                obj.get_type().name()?
+            ))
+            .into());
+        }
+        self.flush()
+    }
+}
+
+/// Serialize a `Value` (whose top level must be an object) to STC text.
+pub fn to_string_with(v: &Value, opts: &DumpOptions) -> Result<String, StcError> {
+    let mut em = Emitter::new(opts);
+    em.value("", v)?;
+    Ok(em.finish())
+}
+
+/// Serialize a `Value` to STC text with default options.
+pub fn to_string(v: &Value) -> Result<String, StcError> {
+    to_string_with(v, &DumpOptions::default())
+}
+
+/// Convert JSON text to STC text, for migrating existing configs. The JSON
+/// must be an object without `null`s; keys that are not identifiers are
+/// quoted.
+///
+/// ```
+/// let stc = stc_rust::from_json_str(r#"{"server": {"ports": [80, 443]}}"#)?;
+/// assert_eq!(stc, "server.ports.$0: 80\nserver.ports.$1: 443");
+/// # Ok::<(), stc_rust::StcError>(())
+/// ```
+pub fn from_json_str(json: &str) -> Result<String, StcError> {
+    let v: Value = serde_json::from_str(json).map_err(StcError::Deserialize)?;
+    to_string(&v)
+}
+
+/// Write `v` as STC to the file at `path`, gzip-compressed when `compress`
+/// is set. `parse_file` reads either kind back.
+///
+/// ```no_run
+/// let v = serde_json::json!({"a": 1});
+/// stc_rust::write_file(&v, "out.stc.gz".as_ref(), &stc_rust::DumpOptions::default(), true)?;
+/// # Ok::<(), stc_rust::StcError>(())
+/// ```
+pub fn write_file(v: &Value, path: &Path, opts: &DumpOptions, compress: bool) -> Result<(), StcError> {
+    let text = to_string_with(v, opts)?;
+    if !compress {
+        return Ok(fs::write(path, text)?);
+    }
+    let mut gz = gzip::encoder(BufWriter::new(File::create(path)?));
+    gz.write_all(text.as_bytes())?;
+    gz.finish()?.flush()?;
+    Ok(())
+}
+
+/// Name of the root file written by `split_to_files`.
+pub const SPLIT_ROOT_FILE: &str = "index.stc";
+
+/// Write each non-empty top-level dict of `v` to `<dir>/<key>.stc`, plus a root
+/// file (`index.stc`) holding the remaining top-level entries and an
+/// `!include` line for every section. A section named `index` is kept in the
+/// root file, since its own file would be the root file. Returns the root file's path.
+pub fn split_to_files(v: &Value, dir: &Path) -> Result<PathBuf, StcError> {
+    let Value::Object(obj) = v else {
+        return Err(ser_err("The top-level value must be a dict."));
+    };
+    let opts = DumpOptions::default();
+    let mut root = Emitter::new(&opts);
+    if obj.is_empty() {
+        root.empty("", false);
+    }
+    fs::create_dir_all(dir)?;
+    for (k, child) in obj {
+        let path = Emitter::child_path("", k)?;
+        let file = format!("{k}.stc");
+        match child {
+            // quoted keys, and a section that would overwrite the root file,
+            // stay in the root file rather than naming a file
+            Value::Object(section) if !section.is_empty() && is_identifier(k) && file != SPLIT_ROOT_FILE => {
+                fs::write(dir.join(&file), to_string_with(child, &opts)?)?;
+                root.include(&path, &file);
+            }
+            _ => root.value(&path, child)?,
+        }
+    }
+    let root_path = dir.join(SPLIT_ROOT_FILE);
+    fs::write(&root_path, root.finish())?;
+    Ok(root_path)
+}
+
+/// Python `min_fence(s)`: see `min_fence_len`.
+#[cfg(feature = "python")]
+#[pyfunction]
+pub(crate) fn min_fence(s: &str) -> usize {
+    min_fence_len(s)
+}
+
+#[cfg(feature = "python")]
+#[pyfunction]
+#[pyo3(signature = (obj, *, sort_keys=false, collapse_integral_floats=false))]
+pub(crate) fn dumps(obj: &Bound<'_, PyAny>, sort_keys: bool, collapse_integral_floats: bool) -> PyResult<String> {
+    let opts = DumpOptions { sort_keys, collapse_integral_floats };
+    let mut em = Emitter::new(&opts);
+    em.pyobj("", obj)?;
+    Ok(em.finish())
+}
+
+/// Write `obj` as STC to `fp`, a filesystem path or an object with a
+/// `write()` method, as the lines are produced. With `compress`, the file at
+/// the path is gzip-compressed.
+#[cfg(feature = "python")]
+#[pyfunction]
+#[pyo3(signature = (obj, fp, *, sort_keys=false, collapse_integral_floats=false, compress=false))]
+pub(crate) fn dump(
+    obj: &Bound<'_, PyAny>,
+    fp: &Bound<'_, PyAny>,
+    sort_keys: bool,
+    collapse_integral_floats: bool,
+    compress: bool,
+) -> PyResult<()> {
+    let opts = DumpOptions { sort_keys, collapse_integral_floats };
+    if fp.hasattr("write")? {
+        if compress {
+            return Err(pyo3::exceptions::PyTypeError::new_err(
+                "`compress=True` needs a path to write to, not a file-like object.",
+            ));
+        }
+        let mut write = |chunk: &str| fp.call_method1("write", (chunk,)).map(drop);
+        return Emitter::streaming(&opts, &mut write).pyobj("", obj);
+    }
+    let path: PathBuf = fp.extract()?;
+    let file = BufWriter::new(File::create(path).map_err(StcError::from)?);
+    if !compress {
+        dump_to(file, &opts, obj)?.flush().map_err(StcError::from)?;
+        return Ok(());
+    }
+    dump_to(gzip::encoder(file), &opts, obj)?.finish().and_then(|mut f| f.flush()).map_err(StcError::from)?;
+    Ok(())
+}
+
+/// Stream `obj` as STC into `out`, returning it for the caller to flush.
+#[cfg(feature = "python")]
+fn dump_to<W: Write>(mut out: W, opts: &DumpOptions, obj: &Bound<'_, PyAny>) -> PyResult<W> {
+    let mut write = |chunk: &str| out.write_all(chunk.as_bytes()).map_err(|e| StcError::from(e).into());
+    Emitter::streaming(opts, &mut write).pyobj("", obj)?;
+    Ok(out)
+}
@@ -0,0 +1,66 @@
+//! C ABI for embedding the parser in other languages.
+//!
+//! Every string crossing the boundary is NUL-terminated UTF-8. Strings
+//! returned by this module are allocated by Rust and must be released with
+//! `stc_free`, never with the caller's `free`.
+
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+
+use crate::{lexeme, parse_stc, ParseOptions};
+
+fn to_c(s: String) -> *mut c_char {
+    // Messages may quote input lines, which could contain NUL bytes.
+    CString::new(s.replace('\0', "\\0")).expect("NUL bytes were escaped").into_raw()
+}
+
+/// Parse the STC document `input` and return it as a newly allocated JSON
+/// string.
+///
+/// On failure, returns null and, if `out_err` is not null, stores a newly
+/// allocated error message in `*out_err`. On success `*out_err` is left
+/// untouched. Both strings are owned by the caller and must be released with
+/// `stc_free`.
+///
+/// # Safety
+///
+/// `input` must be null or point to a NUL-terminated string, and `out_err`
+/// must be null or valid for a pointer-sized write.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn stc_parse_json(input: *const c_char, out_err: *mut *mut c_char) -> *mut c_char {
+    let result = if input.is_null() {
+        Err("input is null".to_string())
+    } else {
+        // SAFETY: the caller guarantees `input` is a NUL-terminated string.
+        match unsafe { CStr::from_ptr(input) }.to_str() {
+            Ok(text) => parse_stc(text)
+                .map_err(|e| e.to_string())
+                .and_then(|v| serde_json::to_string(&lexeme::JsonOut(&v, &ParseOptions::default())).map_err(|e| e.to_string())),
+            Err(e) => Err(format!("input is not valid UTF-8: {e}")),
+        }
+    };
+    match result {
+        Ok(json) => to_c(json),
+        Err(msg) => {
+            if !out_err.is_null() {
+                // SAFETY: the caller guarantees `out_err` is writable.
+                unsafe { *out_err = to_c(msg) };
+            }
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Release a string returned by `stc_parse_json`. Null is ignored.
+///
+/// # Safety
+///
+/// `ptr` must be null or a pointer returned by this library that has not
+/// already been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn stc_free(ptr: *mut c_char) {
+    if !ptr.is_null() {
+        // SAFETY: `ptr` came from `CString::into_raw` in this module.
+        drop(unsafe { CString::from_raw(ptr) });
+    }
+}
@@ -0,0 +1,37 @@
+//! Gzip-compressed STC files, conventionally named `.stc.gz`. Compression is
+//! detected from the gzip magic bytes rather than the extension, so
+//! `parse_file` reads compressed and plain files alike.
+
+use std::io::{Read, Write};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::StcError;
+
+/// The first two bytes of every gzip stream.
+const MAGIC: &[u8] = b"\x1f\x8b";
+
+/// Decompress `data` if it is gzip, or return it unchanged.
+pub(crate) fn decompress(data: Vec<u8>) -> Result<Vec<u8>, StcError> {
+    if !data.starts_with(MAGIC) {
+        return Ok(data);
+    }
+    let mut out = Vec::with_capacity(data.len() * 4);
+    GzDecoder::new(data.as_slice()).read_to_end(&mut out)?;
+    Ok(out)
+}
+
+/// Wrap `w` so that what is written to it is gzip-compressed. Call `finish`
+/// on the encoder to write the gzip trailer.
+pub(crate) fn encoder<W: Write>(w: W) -> GzEncoder<W> {
+    GzEncoder::new(w, Compression::default())
+}
+
+/// Python `gunzip(data)`: `data` decompressed if it is gzip, else unchanged.
+#[cfg(feature = "python")]
+#[pyo3::pyfunction]
+pub(crate) fn gunzip(data: Vec<u8>) -> Result<std::borrow::Cow<'static, [u8]>, StcError> {
+    decompress(data).map(std::borrow::Cow::Owned)
+}
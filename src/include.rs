@@ -0,0 +1,166 @@
+//! `key: !include path` values, which splice another STC file's document in
+//! as the key's value. Paths resolve relative to the including file, so
+//! includes only work when parsing a file (`parse_file`), and only with
+//! `ParseOptions::allow_includes`, since untrusted input must not be able to
+//! read arbitrary files.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use crate::{base64, err, gzip, key_segment, lexeme, EmptyObject, ErrorKind, Node, ParseOptions, StcError, StcParser};
+
+/// Value prefix of an include directive.
+pub(crate) const INCLUDE_PREFIX: &str = "!include";
+
+/// Parse the STC file at `path`, resolving `!include` values relative to it
+/// when `opts.allow_includes` is set. Gzip-compressed files, the file itself
+/// or included ones, are decompressed.
+///
+/// ```no_run
+/// let opts = stc_rust::ParseOptions::new().allow_includes(true);
+/// let config = stc_rust::parse_file("config/index.stc".as_ref(), &opts)?;
+/// # Ok::<(), stc_rust::StcError>(())
+/// ```
+pub fn parse_file(path: &Path, opts: &ParseOptions) -> Result<Value, StcError> {
+    let includes = Includes { stack: Vec::new(), read: None };
+    parse_nested(fs::canonicalize(path)?, includes, opts, false, (0, 0)).map(|(value, _)| value)
+}
+
+/// Reads the text of a file about to be parsed by `parse_file_with`.
+pub(crate) type ReadFile = Arc<dyn Fn(&Path) -> Result<String, StcError> + Send + Sync>;
+
+/// The files being parsed, outermost first, and how to read them. Empty when
+/// the parser is not reading a file.
+#[derive(Clone, Default)]
+pub(crate) struct Includes {
+    stack: Vec<PathBuf>,
+    read: Option<ReadFile>,
+}
+
+/// `parse_file` for the Python bindings: lexeme markers are kept, and the
+/// file and every included file are read with `read` when given, e.g. to
+/// decompress them.
+#[cfg(feature = "python")]
+pub(crate) fn parse_file_with(path: &Path, opts: &ParseOptions, read: Option<ReadFile>) -> Result<Value, StcError> {
+    let includes = Includes { stack: Vec::new(), read };
+    parse_nested(fs::canonicalize(path)?, includes, opts, true, (0, 0)).map(|(value, _)| value)
+}
+
+/// Read `path` as UTF-8, decompressing it if it is gzip, and naming the byte
+/// offset of the first invalid sequence.
+fn read_utf8(path: &Path) -> Result<String, StcError> {
+    String::from_utf8(gzip::decompress(fs::read(path)?)?).map_err(|e| {
+        let at = e.utf8_error().valid_up_to();
+        let msg = format!("{} is not valid UTF-8: invalid sequence at byte offset {at}", path.display());
+        StcError::Io(io::Error::new(io::ErrorKind::InvalidData, msg))
+    })
+}
+
+/// Parse the file at `path` as part of a document that has already used
+/// `(lines, bytes)`, so `max_lines` and `max_bytes` count all of its files.
+/// Returns the value and what the document has used once the file is read.
+fn parse_nested(
+    path: PathBuf,
+    mut includes: Includes,
+    opts: &ParseOptions,
+    keep_lexemes: bool,
+    used: (usize, usize),
+) -> Result<(Value, (usize, usize)), StcError> {
+    let text = includes.read.as_ref().map_or_else(|| read_utf8(&path), |read| read(&path))?;
+    includes.stack.push(path);
+    let mut parser = StcParser { keep_lexemes, ..StcParser::with_options(opts.clone()) };
+    parser.includes = includes;
+    (parser.other_lines, parser.bytes) = used;
+    for raw_line in opts.lines(&text) {
+        parser.feed_line(raw_line)?;
+    }
+    let used = (parser.lines_used(), parser.bytes);
+    Ok((parser.finish()?, used))
+}
+
+/// The node for `key: !include <target>` on line `ln` of the file `parser`
+/// is reading, and the `(lines, bytes)` the document has used once the
+/// included file is read.
+pub(crate) fn resolve(target: &str, parser: &StcParser, ln: usize) -> Result<(Node, (usize, usize)), StcError> {
+    let (includes, opts) = (&parser.includes, &parser.opts);
+    let stack = &includes.stack;
+    if !opts.allow_includes {
+        return Err(err(
+            ErrorKind::InvalidValue,
+            format!("Invalid value: {INCLUDE_PREFIX} {target}. Includes are disabled; set `allow_includes` to enable them."),
+            Some(ln),
+        ));
+    }
+    let Some(current) = stack.last() else {
+        return Err(err(
+            ErrorKind::InvalidValue,
+            format!("Cannot include `{target}`: includes resolve relative to a file, so parse with `parse_file`."),
+            Some(ln),
+        ));
+    };
+    if target.is_empty() {
+        return Err(err(ErrorKind::MissingValue, format!("Missing file name after `{INCLUDE_PREFIX}`."), Some(ln)));
+    }
+    let joined = current.parent().unwrap_or(Path::new("")).join(target);
+    let path = fs::canonicalize(&joined).map_err(|e| {
+        err(ErrorKind::InvalidValue, format!("Cannot include `{target}`: {e}."), Some(ln))
+    })?;
+    if let Some(start) = stack.iter().position(|p| *p == path) {
+        let chain: Vec<String> = stack[start..].iter().chain([&path]).map(|p| file_name(p)).collect();
+        return Err(err(ErrorKind::IncludeCycle, format!("Include cycle: {}.", chain.join(" -> ")), Some(ln)));
+    }
+    // `required` paths are checked once, on the document the includes make up
+    let nested = ParseOptions { required: Vec::new(), ..opts.clone() };
+    let (value, used) = parse_nested(path, includes.clone(), &nested, parser.keep_lexemes, (parser.lines_used(), parser.bytes)).map_err(|e| match e {
+        StcError::Parse { kind, .. } => {
+            err(kind, format!("In included file `{target}`: {e}"), Some(ln))
+        }
+        StcError::Io(io) => err(ErrorKind::InvalidValue, format!("Cannot include `{target}`: {io}."), Some(ln)),
+        other => other,
+    })?;
+    Ok((value_to_node(value, ln), used))
+}
+
+fn file_name(p: &Path) -> String {
+    p.file_name().map_or_else(|| p.display().to_string(), |n| n.to_string_lossy().into_owned())
+}
+
+/// Rebuild the parse tree of an included document, so later lines can still
+/// add keys to an included dict and `$N` items to an included list.
+fn value_to_node(v: Value, ln: usize) -> Node {
+    if let Some(raw) = lexeme::lexeme_of(&v) {
+        return Node::Lexeme(raw.to_string());
+    }
+    if let Some(data) = base64::bytes_of(&v).and_then(base64::decode) {
+        return Node::Bytes(data);
+    }
+    match v {
+        Value::Null => Node::Null,
+        Value::Bool(b) => Node::Bool(b),
+        Value::Number(n) => n.as_i64().map_or_else(|| Node::Float(n.as_f64().unwrap_or(f64::NAN)), Node::Int),
+        Value::String(s) => Node::Str(s),
+        Value::Array(arr) if arr.is_empty() => Node::Empty(EmptyObject::EmptyList),
+        Value::Object(obj) if obj.is_empty() => Node::Empty(EmptyObject::EmptyDict),
+        Value::Array(arr) => map_node(arr.into_iter().enumerate().map(|(i, v)| (format!("${i}"), v)), ln),
+        // map keys are held as key segments, quoted unless identifiers
+        Value::Object(obj) => map_node(obj.into_iter().map(|(k, v)| (key_segment(&k).into_owned(), v)), ln),
+    }
+}
+
+fn map_node(entries: impl Iterator<Item = (String, Value)>, ln: usize) -> Node {
+    let mut node = Node::new_map(Some(ln));
+    let Node::Map(map, lines) = &mut node else { unreachable!("new_map builds a map") };
+    for (k, v) in entries {
+        let child = value_to_node(v, ln);
+        lines.note(&k, ln);
+        if !matches!(child, Node::Map(..)) {
+            lines.values.insert(k.clone(), ln);
+        }
+        map.insert(k, child);
+    }
+    node
+}
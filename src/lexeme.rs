@@ -0,0 +1,181 @@
+//! Exact number lexemes, kept when `preserve_number_lexemes` is set.
+//!
+//! A number whose source text differs from the way `dumps` would write it
+//! (`1.50`, `+5`, `1e3`) is carried through the `Value` tree as a one-entry
+//! object `{LEXEME_KEY: "<text>"}`. The key is not a valid identifier, so it
+//! can never collide with a parsed dict. Python sees an `int` / `float`
+//! subclass remembering the text, and both emitters write it back verbatim.
+
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+use serde_json::value::RawValue;
+use serde_json::{Map, Number, Value};
+
+use crate::{base64, parse_number, Node, ParseOptions};
+
+/// Reserved object key marking a preserved number lexeme.
+pub const LEXEME_KEY: &str = "\u{0}lexeme";
+
+/// The text `dumps` would write for a parsed number.
+fn canonical(n: &Node) -> Option<String> {
+    match n {
+        Node::Int(i) => Some(i.to_string()),
+        Node::Float(f) => Some(format!("{f:?}")),
+        _ => None,
+    }
+}
+
+/// Whether `raw`, parsed as `n`, needs its text kept to round-trip unchanged.
+pub(crate) fn needs_preserving(raw: &str, n: &Node) -> bool {
+    canonical(n).is_some_and(|c| c != raw)
+}
+
+pub(crate) fn marker(raw: String) -> Value {
+    let mut m = Map::new();
+    m.insert(LEXEME_KEY.to_string(), Value::String(raw));
+    Value::Object(m)
+}
+
+/// The preserved text if `v` is a lexeme marker.
+pub(crate) fn lexeme_of(v: &Value) -> Option<&str> {
+    match v {
+        Value::Object(m) if m.len() == 1 => m.get(LEXEME_KEY)?.as_str(),
+        _ => None,
+    }
+}
+
+/// The number text of a lexeme. Backticked lexemes come from `strict_types`.
+fn number_text(raw: &str) -> &str {
+    raw.strip_prefix('`').and_then(|r| r.strip_suffix('`')).unwrap_or(raw)
+}
+
+/// The number a lexeme stands for, typed by the options it was parsed with
+/// (`1e3` is an int under `scientific_ints`).
+pub(crate) fn lexeme_node(raw: &str, opts: &ParseOptions) -> Option<Node> {
+    parse_number(number_text(raw), opts)
+}
+
+/// Serializes a `Value` with every lexeme marker written as its source text
+/// when that text is a valid JSON number (`1.50`, `1e2`), and as the number
+/// `opts` parse it to otherwise (`+5`, `.5`). Bytes markers are written as
+/// base64 strings.
+pub(crate) struct JsonOut<'a>(pub(crate) &'a Value, pub(crate) &'a ParseOptions);
+
+impl Serialize for JsonOut<'_> {
+    fn serialize<S: Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        if let Some(raw) = lexeme_of(self.0) {
+            if let Ok(token) = RawValue::from_string(number_text(raw).to_string())
+                && token.get().starts_with(|c: char| c == '-' || c.is_ascii_digit())
+            {
+                return token.serialize(ser);
+            }
+            return match lexeme_node(raw, self.1) {
+                Some(Node::Int(i)) => ser.serialize_i64(i),
+                Some(Node::Float(f)) => Number::from_f64(f).serialize(ser),
+                _ => ser.serialize_str(raw),
+            };
+        }
+        if let Some(text) = base64::bytes_of(self.0) {
+            return ser.serialize_str(text);
+        }
+        match self.0 {
+            Value::Array(arr) => {
+                let mut seq = ser.serialize_seq(Some(arr.len()))?;
+                for v in arr {
+                    seq.serialize_element(&JsonOut(v, self.1))?;
+                }
+                seq.end()
+            }
+            Value::Object(obj) => {
+                let mut map = ser.serialize_map(Some(obj.len()))?;
+                for (k, v) in obj {
+                    map.serialize_entry(k, &JsonOut(v, self.1))?;
+                }
+                map.end()
+            }
+            other => other.serialize(ser),
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+pub(crate) use py::{py_lexeme, to_py};
+
+#[cfg(feature = "python")]
+mod py {
+    use pyo3::prelude::*;
+    use pyo3::sync::GILOnceCell;
+    use pyo3::types::PyType;
+
+    use super::{lexeme_node, number_text};
+    use crate::{Node, ParseOptions};
+
+    const CLASSES: &str = r#"
+class LexemeInt(int):
+    """An int that remembers the exact text it was parsed from."""
+
+    def __new__(cls, value, lexeme):
+        self = super().__new__(cls, value)
+        self.lexeme = lexeme
+        return self
+
+
+class LexemeFloat(float):
+    """A float that remembers the exact text it was parsed from."""
+
+    def __new__(cls, value, lexeme):
+        self = super().__new__(cls, value)
+        self.lexeme = lexeme
+        return self
+"#;
+
+    static LEXEME_INT: GILOnceCell<Py<PyType>> = GILOnceCell::new();
+    static LEXEME_FLOAT: GILOnceCell<Py<PyType>> = GILOnceCell::new();
+    static DECIMAL: GILOnceCell<Py<PyType>> = GILOnceCell::new();
+
+    fn class<'py>(py: Python<'py>, cell: &'static GILOnceCell<Py<PyType>>, name: &str) -> PyResult<&'py Bound<'py, PyType>> {
+        cell.get_or_try_init(py, || {
+            let m = PyModule::from_code(py, &std::ffi::CString::new(CLASSES)?, c"stc_lexeme.py", c"stc.stc_rust")?;
+            Ok::<_, PyErr>(m.getattr(name)?.downcast_into::<PyType>()?.unbind())
+        })
+        .map(|t| t.bind(py))
+    }
+
+    fn lexeme_int(py: Python<'_>) -> PyResult<&Bound<'_, PyType>> {
+        class(py, &LEXEME_INT, "LexemeInt")
+    }
+
+    fn lexeme_float(py: Python<'_>) -> PyResult<&Bound<'_, PyType>> {
+        class(py, &LEXEME_FLOAT, "LexemeFloat")
+    }
+
+    /// Build the Python number for a preserved lexeme, typed as `opts` parse
+    /// it. With `use_decimal`, floats become `decimal.Decimal`s built from the
+    /// source text.
+    pub(crate) fn to_py(py: Python<'_>, raw: &str, use_decimal: bool, opts: &ParseOptions) -> PyResult<PyObject> {
+        Ok(match lexeme_node(raw, opts) {
+            Some(Node::Float(_)) if use_decimal => DECIMAL.import(py, "decimal", "Decimal")?.call1((number_text(raw),))?.unbind(),
+            Some(Node::Int(i)) => lexeme_int(py)?.call1((i, raw))?.unbind(),
+            Some(Node::Float(f)) => lexeme_float(py)?.call1((f, raw))?.unbind(),
+            _ => return Err(crate::STCParseError::new_err(format!("Invalid number lexeme `{raw}`"))),
+        })
+    }
+
+    /// The text to write for `obj` if it is a lexeme number whose text still
+    /// parses to its value.
+    pub(crate) fn py_lexeme(obj: &Bound<'_, PyAny>) -> PyResult<Option<String>> {
+        let py = obj.py();
+        let is_int = obj.is_instance(lexeme_int(py)?)?;
+        if !is_int && !obj.is_instance(lexeme_float(py)?)? {
+            return Ok(None);
+        }
+        let raw: String = obj.getattr("lexeme")?.extract()?;
+        // an int may have been read from float text such as `1e3` or `2.0`
+        let opts = ParseOptions::new().float_to_int_when_whole(is_int);
+        let same = match lexeme_node(&raw, &opts) {
+            Some(Node::Int(i)) if is_int => obj.extract::<i64>().ok() == Some(i),
+            Some(Node::Float(f)) if !is_int => obj.extract::<f64>().ok() == Some(f),
+            _ => false,
+        };
+        Ok(same.then_some(raw))
+    }
+}
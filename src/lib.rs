@@ -1,140 +1,754 @@
-use pyo3::prelude::*;
+use serde::de::DeserializeOwned;
 use serde_json::{Map, Number, Value};
-use std::collections::HashMap;
-use pyo3::{create_exception, exceptions::PyException, PyErr};
-use pyo3::types::{PyBool, PyDict, PyFloat, PyList, PyInt, PyString};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-create_exception!(stc_rust, STCParseError, PyException);
+mod base64;
+mod builder;
+#[cfg(feature = "python")]
+mod cache;
+mod dumps;
+mod ffi;
+mod gzip;
+mod include;
+mod lexeme;
+mod lint;
+mod merge;
+#[cfg(feature = "python")]
+mod python;
+mod query;
+#[cfg(feature = "python")]
+mod reader;
+mod toml;
+#[cfg(feature = "wasm")]
+mod wasm;
 
-// Helper that builds a PyErr of type STCParseError
-fn err<S: Into<String>>(s: S, ln: Option<usize>) -> PyErr {
-    let m = s.into();
-    let msg = if let Some(ln) = ln {
-        format!("Line {ln}: {m}")
-    } else {
-        m
-    };
-    STCParseError::new_err(msg) // returns PyErr
+#[cfg(feature = "python")]
+pub(crate) use python::{value_to_pyobj, ToPyOptions, STCParseError};
+
+pub use base64::BYTES_KEY;
+pub use builder::DocumentBuilder;
+pub use dumps::{from_json_str, min_fence_len, split_to_files, to_string, to_string_with, write_file, DumpOptions, SPLIT_ROOT_FILE};
+pub use ffi::{stc_free, stc_parse_json};
+pub use include::parse_file;
+pub use lexeme::LEXEME_KEY;
+pub use lint::{lint, Warning};
+pub use merge::{merge_values, merge_values_with, MergeOptions};
+pub use query::values_of_type;
+pub use toml::{to_toml, to_toml_with, value_to_toml};
+#[cfg(feature = "wasm")]
+pub use wasm::parse_to_js;
+
+/// What kind of problem a parse error reports, exposed to Python as
+/// `STCParseError.kind`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    MissingColon,
+    InvalidKey,
+    InvalidValue,
+    /// Nothing follows the key's `:`.
+    MissingValue,
+    InvalidIndex,
+    TypeMismatch,
+    /// The same key is used both as a value and as a list or dict.
+    KeyConflict,
+    DuplicateValue,
+    UnclosedString,
+    InvalidBlockContent,
+    /// The input is longer than `ParseOptions::max_bytes` or `max_lines`, a
+    /// list is longer than `max_list_len`, or an index exceeds `MAX_LIST_INDEX`.
+    LimitExceeded,
+    /// A path listed in `ParseOptions::required` is not in the document.
+    MissingRequired,
+    /// An `!include` chain leads back to a file that is already being parsed.
+    IncludeCycle,
+    Internal,
 }
 
-#[derive(Debug, Clone)]
+impl ErrorKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorKind::MissingColon => "missing_colon",
+            ErrorKind::InvalidKey => "invalid_key",
+            ErrorKind::InvalidValue => "invalid_value",
+            ErrorKind::MissingValue => "missing_value",
+            ErrorKind::InvalidIndex => "invalid_index",
+            ErrorKind::TypeMismatch => "type_mismatch",
+            ErrorKind::KeyConflict => "key_conflict",
+            ErrorKind::DuplicateValue => "duplicate_value",
+            ErrorKind::UnclosedString => "unclosed_string",
+            ErrorKind::InvalidBlockContent => "invalid_block_content",
+            ErrorKind::LimitExceeded => "limit_exceeded",
+            ErrorKind::MissingRequired => "missing_required",
+            ErrorKind::IncludeCycle => "include_cycle",
+            ErrorKind::Internal => "internal",
+        }
+    }
+}
+
+/// Errors produced by the native parser.
+#[derive(Debug)]
+pub enum StcError {
+    /// The input is not valid STC. `line` and `column` are 1-based.
+    Parse { message: String, line: Option<usize>, column: Option<usize>, kind: ErrorKind },
+    /// The input is valid STC but does not match the requested Rust type.
+    Deserialize(serde_json::Error),
+    /// A value cannot be written out, as STC or as JSON.
+    Serialize(String),
+    /// Reading or writing a file failed.
+    Io(std::io::Error),
+    /// An argument to an API function was out of range.
+    InvalidArgument(String),
+}
+
+impl From<std::io::Error> for StcError {
+    fn from(e: std::io::Error) -> Self {
+        StcError::Io(e)
+    }
+}
+
+impl StcError {
+    /// Point a parse error at a 1-based `column`, unless it already has one.
+    fn at_column(mut self, col: usize) -> Self {
+        if let StcError::Parse { column: c @ None, .. } = &mut self {
+            *c = Some(col);
+        }
+        self
+    }
+
+    /// Shift a parse error's column by `by` characters, for errors found in
+    /// a slice of the line.
+    fn shift_column(mut self, by: usize) -> Self {
+        if let StcError::Parse { column: Some(c), .. } = &mut self {
+            *c += by;
+        }
+        self
+    }
+
+    /// Append `raw_line` and a caret under the error's column, as rustc does.
+    /// Only errors reported on line `ln` get a snippet.
+    fn with_snippet(mut self, raw_line: &str, ln: usize) -> Self {
+        if let StcError::Parse { message, line: Some(at), column, .. } = &mut self
+            && *at == ln
+        {
+            let gutter = " ".repeat(ln.to_string().len());
+            message.push_str(&format!("\n{gutter} |\n{ln} | {raw_line}"));
+            if let Some(col) = column {
+                // keep tabs so the caret lines up however the terminal renders them
+                let pad: String = raw_line.chars().take(*col - 1).map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+                message.push_str(&format!("\n{gutter} | {pad}^"));
+            }
+        }
+        self
+    }
+
+    /// A failure to write a parsed document as JSON.
+    pub(crate) fn json(e: serde_json::Error) -> Self {
+        StcError::Serialize(format!("Cannot write the document as JSON: {e}"))
+    }
+}
+
+/// The message Python's `STCParseError` shows: parse errors are prefixed with
+/// `Line N: ` when they have a line.
+impl std::fmt::Display for StcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StcError::Parse { message, line: Some(ln), .. } => write!(f, "Line {ln}: {message}"),
+            StcError::Parse { message, line: None, .. } => f.write_str(message),
+            StcError::Deserialize(e) => e.fmt(f),
+            StcError::Serialize(m) => f.write_str(m),
+            StcError::Io(e) => e.fmt(f),
+            StcError::InvalidArgument(m) => f.write_str(m),
+        }
+    }
+}
+
+impl std::error::Error for StcError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StcError::Deserialize(e) => Some(e),
+            StcError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+// Helper that builds a StcError::Parse
+fn err<S: Into<String>>(kind: ErrorKind, s: S, ln: Option<usize>) -> StcError {
+    StcError::Parse { message: s.into(), line: ln, column: None, kind }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 enum EmptyObject {
     EmptyList,
     EmptyDict,
 }
 
+/// Where a map's entries came from, for error messages.
+#[derive(Debug, Clone, Default)]
+struct MapLines {
+    /// The line that first created the map (`None` for the root).
+    created: Option<usize>,
+    /// The first line that added a `$N` entry.
+    first_index: Option<usize>,
+    /// The first line that added a named entry.
+    first_key: Option<usize>,
+    /// The line that set each value (non-map) entry.
+    values: HashMap<String, usize>,
+    /// Where each value entry's text is, when the parser records spans.
+    spans: HashMap<String, Span>,
+}
+
+/// Where a value is written in the source, from `(line, col)` to
+/// `(end_line, end_col)`. Lines and columns count from 1, like error
+/// positions, and `end_col` is the column just past the value's last
+/// character. A string block spans from its opening fence to its closing one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub line: usize,
+    pub col: usize,
+    pub end_line: usize,
+    pub end_col: usize,
+}
+
+impl MapLines {
+    fn note(&mut self, key: &str, ln: usize) {
+        let slot = if key.starts_with('$') { &mut self.first_index } else { &mut self.first_key };
+        slot.get_or_insert(ln);
+    }
+}
+
 #[derive(Debug, Clone)]
 enum Node {
-    Map(HashMap<String, Node>),
+    /// Children, and the lines they came from.
+    Map(HashMap<String, Node>, MapLines),
     Bool(bool),
     Int(i64),
     Float(f64),
     Str(String),
     Empty(EmptyObject),
+    /// An empty value under `EmptyValues::Null`.
+    Null,
+    /// An inline list, `key: [1, 2]`. It cannot be extended by `key.$N` lines.
+    List(Vec<Node>),
+    /// The decoded content of a base64 block.
+    Bytes(Vec<u8>),
+    /// A number kept as its source text (see `ParseOptions::preserve_number_lexemes`).
+    Lexeme(String),
 }
 
 impl Node {
-    fn new_map() -> Self {
-        Node::Map(HashMap::new())
+    fn new_map(ln: Option<usize>) -> Self {
+        Node::Map(HashMap::new(), MapLines { created: ln, ..MapLines::default() })
     }
-    fn as_map_mut(&mut self) -> Result<&mut HashMap<String, Node>, PyErr> {
+    fn as_map_mut(&mut self) -> Result<(&mut HashMap<String, Node>, &mut MapLines), StcError> {
         match self {
-            Node::Map(m) => Ok(m),
-            _ => Err(err("Internal: expected map node", None)),
+            Node::Map(m, lines) => Ok((m, lines)),
+            _ => Err(err(ErrorKind::Internal, "Internal: expected map node", None)),
         }
     }
 }
 
-fn value_to_pyobj(py: Python<'_>, v: &Value) -> PyResult<PyObject> {
-    Ok(match v {
-        Value::Null => py.None().into(), // Py<PyAny> == PyObject
+pub(crate) fn is_identifier(piece: &str) -> bool {
+    // A pragmatic approximation of Python's str.isidentifier():
+    // ASCII [A-Za-z_][A-Za-z0-9_]*  (adjust if you need full Unicode idents)
+    let mut chars = piece.chars();
+    match chars.next() {
+        Some(c) if c == '_' || c.is_ascii_alphabetic() => (),
+        _ => return false,
+    }
+    chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+}
 
-        Value::Bool(b) => {
-            // Bound<PyAny>
-            let any = <pyo3::Bound<'_, PyBool> as Clone>::clone(&PyBool::new(py, *b)).into_any();
-            // If you need Py<PyAny>, call .unbind():
-            // let obj: Py<PyAny> = any.unbind();
-            any.into()
-        }
+/// Largest `$N` (or `$-N`) a key may use. Lists longer than this are well
+/// outside what STC is meant for, and rejecting the index on its own line is
+/// clearer than failing later on a missing index.
+pub const MAX_LIST_INDEX: u64 = 10_000_000;
 
-        Value::Number(num) => {
-            if let Some(i) = num.as_i64() {
-                PyInt::new(py, i).into_any().unbind()
-            } else if let Some(u) = num.as_u64() {
-                PyInt::new(py, u).into_any().unbind()
-            } else if let Some(f) = num.as_f64() {
-                PyFloat::new(py, f).into_any().unbind()
-            } else {
-                return Err(STCParseError::new_err("Invalid JSON number"));
-            }
+/// A bare `$` segment, which the parser replaces with the list's next index.
+const AUTO_INDEX: &str = "$";
+
+/// A dict key as an STC key segment: bare if it is an identifier, otherwise
+/// quoted, as in `"a.b"` or `"$x"`, with the escapes of quoted values.
+pub(crate) fn key_segment(key: &str) -> Cow<'_, str> {
+    if is_identifier(key) {
+        return Cow::Borrowed(key);
+    }
+    let mut out = String::with_capacity(key.len() + 2);
+    out.push('"');
+    for c in key.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
         }
+    }
+    out.push('"');
+    Cow::Owned(out)
+}
 
-        Value::String(s) => PyString::new(py, s).into_any().unbind(),
+/// The dict key a segment returned by `parse_key` stands for.
+pub(crate) fn decode_segment(seg: &str) -> Cow<'_, str> {
+    match seg.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        Some(inner) => Cow::Owned(unquote(inner, None).expect("parse_key only returns valid quoted segments")),
+        None => Cow::Borrowed(seg),
+    }
+}
 
-        Value::Array(arr) => {
-            let list = PyList::empty(py);
-            for item in arr {
-                // value_to_pyobj -> PyObject, bind to this GIL to append
-                list.append(value_to_pyobj(py, item)?.bind(py))?;
+/// Split `key` on the dots outside quoted segments.
+fn split_key(key: &str) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let (mut start, mut in_quote, mut escaped) = (0, false, false);
+    for (i, c) in key.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_quote => escaped = true,
+            '"' if in_quote => in_quote = false,
+            '"' if i == start => in_quote = true,
+            '.' if !in_quote => {
+                pieces.push(&key[start..i]);
+                start = i + 1;
             }
-            list.into_any().unbind()
+            _ => {}
         }
+    }
+    pieces.push(&key[start..]);
+    pieces
+}
 
-        Value::Object(obj) => {
-            let dict = PyDict::new(py);
-            for (k, val) in obj {
-                dict.set_item(k, value_to_pyobj(py, val)?.bind(py))?;
-            }
-            dict.into_any().unbind()
+/// Byte offset of the `"` closing the quoted key segment that `piece` starts
+/// with, if it is closed.
+fn closing_quote(piece: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in piece.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return Some(i),
+            _ => {}
         }
-    })
+    }
+    None
 }
 
-fn is_identifier(piece: &str) -> bool {
-    // A pragmatic approximation of Python's str.isidentifier():
-    // ASCII [A-Za-z_][A-Za-z0-9_]*  (adjust if you need full Unicode idents)
-    let mut chars = piece.chars();
-    match chars.next() {
-        Some(c) if c == '_' || c.is_ascii_alphabetic() => (),
-        _ => return false,
+/// Byte offset of the first `sep` outside a quoted key segment. Indentation
+/// is skipped first, so a tab separator does not match a leading tab.
+fn find_separator(line: &str, sep: &str) -> Option<usize> {
+    let (mut in_quote, mut escaped, mut segment_start) = (false, false, true);
+    let indent = line.len() - line.trim_start().len();
+    for (i, c) in line.char_indices().skip_while(|&(i, _)| i < indent) {
+        if in_quote {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_quote = false,
+                _ => {}
+            }
+            continue;
+        }
+        if line[i..].starts_with(sep) {
+            return Some(i);
+        }
+        match c {
+            '"' if segment_start => (in_quote, segment_start) = (true, false),
+            '.' => segment_start = true,
+            c if c.is_whitespace() => {}
+            _ => segment_start = false,
+        }
     }
-    chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+    // an unclosed quote is reported by `parse_key`
+    line.find(sep)
 }
 
-fn parse_key(key: &str, ln: Option<usize>) -> Result<Vec<String>, PyErr> {
+/// Split a key into its path segments, borrowing from `key` except for
+/// quoted segments, which come back in the canonical form of `key_segment`
+/// (`"x"` becomes `x`). Auto indices are left as `$` for the parser to resolve.
+/// Errors point at the column of the offending segment within `key`.
+fn parse_key(key: &str, ln: Option<usize>) -> Result<Vec<Cow<'_, str>>, StcError> {
     let mut path = Vec::new();
-    for piece in key.split('.') {
+    let mut col = 1;
+    for piece in split_key(key) {
+        let at = col;
+        col += piece.chars().count() + 1;
+        let err = |kind, msg| err(kind, msg, ln).at_column(at);
         if piece.is_empty() {
-            return Err(err(format!("Invalid key: {key}. Key must be a valid identifier."), ln));
+            return Err(err(ErrorKind::InvalidKey, format!("Invalid key: {key}. Key must be a valid identifier.")));
         }
-        if piece.starts_with('$') {
-            let idx = &piece[1..];
-            if idx.is_empty() || !idx.chars().all(|c| c.is_ascii_digit()) {
+        if piece.starts_with('"') {
+            let Some(close) = closing_quote(piece) else {
+                return Err(err(ErrorKind::InvalidKey, format!("Invalid key: {key}. Quoted key segment `{piece}` is not closed.")));
+            };
+            let (quoted, rest) = piece.split_at(close + 1);
+            if !rest.is_empty() {
+                let why = if rest.trim().is_empty() {
+                    "whitespace; remove it, or move it inside the quotes".to_string()
+                } else {
+                    format!("`{rest}`; a `\"` inside the segment must be escaped as `\\\"`")
+                };
+                let msg = format!("Invalid key: {key}. Quoted key segment `{quoted}` is followed by {why}.");
+                return Err(crate::err(ErrorKind::InvalidKey, msg, ln).at_column(at + quoted.chars().count()));
+            }
+            let name = unquote(&quoted[1..close], None).map_err(|e| match e {
+                StcError::Parse { message, .. } => err(ErrorKind::InvalidKey, format!("Invalid key: {key}. {message}")),
+                other => other,
+            })?;
+            if name.contains('\0') {
+                return Err(err(ErrorKind::InvalidKey, format!("Invalid key: {}. Key segments cannot contain NUL.", key.escape_debug())));
+            }
+            path.push(Cow::Owned(key_segment(&name).into_owned()));
+        } else if piece == AUTO_INDEX {
+            path.push(Cow::Borrowed(piece));
+        } else if let Some(idx) = piece.strip_prefix('$') {
+            // `$-N` counts from the end of the list
+            let digits = idx.strip_prefix('-').unwrap_or(idx);
+            if digits.is_empty() {
                 return Err(err(
-                    format!("Invalid key: {key}. List index must be $numeric."),
-                    ln,
+                    ErrorKind::InvalidKey,
+                    format!("Invalid key: {key}. List index `{piece}` is missing its number."),
                 ));
             }
-            path.push(piece.to_string());
+            if !digits.chars().all(|c| c.is_ascii_digit()) {
+                return Err(err(
+                    ErrorKind::InvalidKey,
+                    format!("Invalid key: {key}. List index must be numeric, found `{idx}`."),
+                ));
+            }
+            if !digits.parse::<u64>().is_ok_and(|n| n <= MAX_LIST_INDEX) {
+                return Err(err(
+                    ErrorKind::LimitExceeded,
+                    format!("Invalid key: {key}. List index `{piece}` is larger than the maximum of {MAX_LIST_INDEX}."),
+                ));
+            }
+            path.push(Cow::Borrowed(piece));
         } else {
+            // Surrounding whitespace was trimmed, so any left is inside a segment.
+            if let Some((i, c, name)) = piece.char_indices().find_map(|(i, c)| format_char_name(c).map(|n| (i, c, n))) {
+                let col = at + piece[..i].chars().count();
+                return Err(crate::err(
+                    ErrorKind::InvalidKey,
+                    format!(
+                        "Invalid key: {}. Key segment `{}` contains U+{:04X} {name}, an invisible character; retype the key.",
+                        Visible(key),
+                        Visible(piece),
+                        c as u32
+                    ),
+                    ln,
+                )
+                .at_column(col));
+            }
+            if let Some(ws) = piece.chars().find(|c| c.is_whitespace()) {
+                let what = if ws == '\t' { "tabs" } else { "whitespace" };
+                return Err(err(
+                    ErrorKind::InvalidKey,
+                    format!("Invalid key: {}. Key segments cannot contain {what}.", key.escape_debug()),
+                ));
+            }
+            if piece.chars().all(|c| c.is_ascii_digit()) {
+                return Err(err(
+                    ErrorKind::InvalidKey,
+                    format!("Invalid key: {key}. A numeric dict key is quoted, `\"{piece}\"`; a list index is `${piece}`."),
+                ));
+            }
             if !is_identifier(piece) {
                 return Err(err(
+                    ErrorKind::InvalidKey,
                     format!("Invalid key: {key}. Key must be a valid identifier."),
-                    ln,
                 ));
             }
-            path.push(piece.to_string());
+            path.push(Cow::Borrowed(piece));
         }
     }
     Ok(path)
 }
 
+/// Type a key can be annotated with, as in `port(int): 8080`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeyType {
+    Int,
+    Float,
+    Str,
+    Bool,
+}
+
+impl KeyType {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "int" => KeyType::Int,
+            "float" => KeyType::Float,
+            "str" => KeyType::Str,
+            "bool" => KeyType::Bool,
+            _ => return None,
+        })
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            KeyType::Int => "int",
+            KeyType::Float => "float",
+            KeyType::Str => "str",
+            KeyType::Bool => "bool",
+        }
+    }
+}
+
+/// Split an optional `(type)` suffix off a key: `port(int)` gives `port` and `KeyType::Int`.
+fn split_annotation(key: &str, ln: Option<usize>) -> Result<(&str, Option<KeyType>), StcError> {
+    let Some((name, tag)) = key.strip_suffix(')').and_then(|k| k.rsplit_once('(')) else {
+        return Ok((key, None));
+    };
+    let Some(ty) = KeyType::from_name(tag.trim()) else {
+        return Err(err(
+            ErrorKind::InvalidKey,
+            format!("Invalid key: {key}. Unknown type annotation `{}`; expected one of int, float, str, bool.", tag.trim()),
+            ln,
+        ));
+    };
+    Ok((name.trim_end(), Some(ty)))
+}
+
+/// The name of a zero-width or formatting character, the usual invisible
+/// copy-paste artifacts; `None` for any other character.
+fn format_char_name(c: char) -> Option<&'static str> {
+    Some(match c {
+        '\u{ad}' => "SOFT HYPHEN",
+        '\u{200b}' => "ZERO WIDTH SPACE",
+        '\u{200c}' => "ZERO WIDTH NON-JOINER",
+        '\u{200d}' => "ZERO WIDTH JOINER",
+        '\u{200e}' => "LEFT-TO-RIGHT MARK",
+        '\u{200f}' => "RIGHT-TO-LEFT MARK",
+        '\u{202a}'..='\u{202e}' => "BIDIRECTIONAL FORMATTING CHARACTER",
+        '\u{2060}' => "WORD JOINER",
+        '\u{feff}' => "BYTE ORDER MARK",
+        _ => return None,
+    })
+}
+
+/// Shows a value in an error message with invisible or ambiguous characters
+/// escaped, e.g. a non-breaking space as `\u{a0}`, so a value that looks
+/// right but is not can be told apart.
+struct Visible<'a>(&'a str);
+
+impl std::fmt::Display for Visible<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use std::fmt::Write;
+        for c in self.0.chars() {
+            let invisible = c.is_control() || (c.is_whitespace() && c != ' ') || format_char_name(c).is_some();
+            if invisible { write!(f, "\\u{{{:x}}}", c as u32)? } else { f.write_char(c)? }
+        }
+        Ok(())
+    }
+}
+
+/// Split a typed empty collection, `[]int` or `{}str`, into the plain `[]` or
+/// `{}` and the element type it names. `None` for any other value.
+fn typed_empty(raw: &str, ln: Option<usize>) -> Result<Option<(&'static str, KeyType)>, StcError> {
+    let Some((empty, tag)) = ["[]", "{}"].into_iter().find_map(|e| raw.strip_prefix(e).map(|t| (e, t))) else {
+        return Ok(None);
+    };
+    if tag.is_empty() || !tag.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Ok(None);
+    }
+    match KeyType::from_name(tag) {
+        Some(ty) => Ok(Some((empty, ty))),
+        None => Err(err(
+            ErrorKind::InvalidValue,
+            format!("Invalid value: {}. Unknown element type `{tag}`; expected one of int, float, str, bool.", Visible(raw)),
+            ln,
+        )),
+    }
+}
+
+/// Reject a value whose type differs from the key's annotation.
+fn check_annotation(key: &str, ty: KeyType, parsed: &ParsedValue, ln: Option<usize>) -> Result<(), StcError> {
+    let found = match parsed {
+        ParsedValue::StringStart { kind: BlockKind::Base64, .. } => "a base64 block",
+        ParsedValue::StringStart { .. } => "a string block",
+        ParsedValue::Immediate(Node::Int(_)) => "an int",
+        ParsedValue::Immediate(Node::Float(_)) => "a float",
+        ParsedValue::Immediate(Node::Bool(_)) => "a bool",
+        ParsedValue::Immediate(Node::Str(_)) => "a quoted string",
+        ParsedValue::Immediate(Node::Empty(EmptyObject::EmptyList)) => "an empty list",
+        ParsedValue::Immediate(Node::Null) => "null",
+        ParsedValue::Immediate(Node::List(_)) => "an inline list",
+        ParsedValue::Immediate(_) => "an empty dict",
+    };
+    let ok = matches!(
+        (ty, parsed),
+        (KeyType::Str, ParsedValue::StringStart { kind: BlockKind::Text | BlockKind::Raw, .. } | ParsedValue::Immediate(Node::Str(_)))
+            | (KeyType::Int, ParsedValue::Immediate(Node::Int(_)))
+            | (KeyType::Float, ParsedValue::Immediate(Node::Float(_)))
+            | (KeyType::Bool, ParsedValue::Immediate(Node::Bool(_)))
+    );
+    if ok {
+        return Ok(());
+    }
+    Err(err(ErrorKind::TypeMismatch, format!("Key `{key}` is annotated `{}` but its value is {found}.", ty.name()), ln))
+}
+
 enum ParsedValue {
     Immediate(Node),
-    StringStart { bt_count: usize },
+    /// Opening fence of a string block: `fence_char` (`` ` `` or `~`) repeated `count` times,
+    /// optionally followed by a content language such as `json`. A leading `r`
+    /// (`` r``` ``) or `b64` (`` b64``` ``) sets the block's `kind`.
+    StringStart { fence_char: char, count: usize, lang: Option<String>, kind: BlockKind },
+}
+
+/// How a string block's content lines become its value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum BlockKind {
+    /// Text without the final newline, subject to `dedent` and the other options.
+    #[default]
+    Text,
+    /// Every content line and its `\n` kept as written.
+    Raw,
+    /// Base64 data, decoded to bytes.
+    Base64,
+}
+
+/// A fence may name its content language, e.g. ```` ```json ````.
+fn is_language_tag(rest: &str) -> bool {
+    rest.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && rest.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '+'))
 }
 
-fn parse_value(raw: &str, ln: Option<usize>) -> Result<ParsedValue, PyErr> {
+/// Check a string block's content against its declared language. Unknown
+/// languages are accepted as-is. `start_ln` is the line of the opening fence.
+fn validate_block(lang: &str, content: &str, start_ln: usize) -> Result<(), StcError> {
+    match lang {
+        "json" => serde_json::from_str::<Value>(content).map(|_| ()).map_err(|e| StcError::Parse {
+            message: format!(
+                "String block tagged `json` is not valid JSON (block line {}, column {}): {e}",
+                e.line(),
+                e.column()
+            ),
+            line: Some(start_ln + e.line()),
+            column: Some(e.column()),
+            kind: ErrorKind::InvalidBlockContent,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// True for plain text such as `hello` or `hello world`, which is almost
+/// always a string whose fences were forgotten.
+fn looks_like_bare_word(raw: &str) -> bool {
+    raw.chars().next().is_some_and(char::is_alphabetic)
+        && raw.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | ' ' | '\''))
+}
+
+/// Decode the inside of a single-line `"..."` value. Supports `\n`, `\t`,
+/// `\\`, `\"` and `\uXXXX`.
+fn unquote(inner: &str, ln: Option<usize>) -> Result<String, StcError> {
+    let bad = |msg: String| err(ErrorKind::InvalidValue, msg, ln);
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Err(bad(format!("Invalid value: \"{inner}\". A `\"` inside a quoted string must be escaped as `\\\"`."))),
+            '\\' => match chars.next() {
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                Some('\\') => out.push('\\'),
+                Some('"') => out.push('"'),
+                Some('u') => {
+                    let hex: String = chars.by_ref().take(4).collect();
+                    let ch = Some(&hex)
+                        .filter(|h| h.len() == 4 && h.chars().all(|c| c.is_ascii_hexdigit()))
+                        .and_then(|h| u32::from_str_radix(h, 16).ok())
+                        .and_then(char::from_u32);
+                    let Some(ch) = ch else {
+                        return Err(bad(format!("Invalid escape `\\u{hex}` in quoted string; expected four hex digits.")));
+                    };
+                    out.push(ch);
+                }
+                Some(other) => {
+                    return Err(bad(format!(
+                        "Invalid escape `\\{other}` in quoted string. Supported escapes are \\n, \\t, \\\\, \\\" and \\uXXXX."
+                    )));
+                }
+                None => return Err(bad("Quoted string ends with a lone `\\`.".to_string())),
+            },
+            c => out.push(c),
+        }
+    }
+    Ok(out)
+}
+
+/// True for integer literals such as `007` whose leading zeros would be dropped.
+fn has_leading_zero(raw: &str) -> bool {
+    let digits = raw.strip_prefix(['+', '-']).unwrap_or(raw);
+    digits.len() > 1 && digits.starts_with('0') && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Plain integers parse as `Int` first. Anything else `f64` accepts, including
+/// scientific notation such as `1e3`, is a `Float`, unless the value is an
+/// exact integer that fits in i64 and either `float_to_int_when_whole` is set
+/// or `scientific_ints` is set and it is written in scientific notation.
+fn parse_number(raw: &str, opts: &ParseOptions) -> Option<Node> {
+    if let Ok(v) = raw.parse::<i64>() {
+        return Some(Node::Int(v));
+    }
+    let f = raw.parse::<f64>().ok()?;
+    let scientific = raw.contains(['e', 'E']);
+    if (opts.float_to_int_when_whole || (opts.scientific_ints && scientific))
+        && let Some(i) = exact_int(raw)
+    {
+        return Some(Node::Int(i));
+    }
+    Some(Node::Float(f))
+}
+
+/// The exact integer a decimal or scientific number (`2.0`, `1e3`, `-1.5e2`)
+/// is equal to, worked out on the decimal digits so no precision is lost
+/// through `f64`.
+fn exact_int(raw: &str) -> Option<i64> {
+    let (mantissa, exp) = raw.split_once(['e', 'E']).unwrap_or((raw, "0"));
+    let exp: i64 = exp.parse().ok()?;
+    let (sign, mantissa) = match mantissa.strip_prefix('-') {
+        Some(m) => ("-", m),
+        None => ("", mantissa.strip_prefix('+').unwrap_or(mantissa)),
+    };
+    let (int_part, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = format!("{int_part}{frac}");
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let shift = exp - frac.len() as i64;
+    let digits = if shift >= 0 {
+        // i64 has at most 19 digits, so a longer result cannot fit
+        if digits.trim_start_matches('0').len() as i64 + shift > 19 {
+            return None;
+        }
+        format!("{digits}{}", "0".repeat(shift as usize))
+    } else {
+        let cut = digits.len().checked_sub(shift.unsigned_abs() as usize)?;
+        if digits[cut..].bytes().any(|b| b != b'0') {
+            return None;
+        }
+        digits[..cut].to_string()
+    };
+    format!("{sign}{}", if digits.is_empty() { "0" } else { &digits }).parse().ok()
+}
+
+/// Parse the text after `key:`. With `strict_types`, only numbers written in
+/// backticks (`` `42` ``) are typed and a bare number is an error.
+fn parse_value(raw: &str, ln: Option<usize>, opts: &ParseOptions) -> Result<ParsedValue, StcError> {
+    let shown = Visible(raw);
+    let (strict_types, sep) = (opts.strict_types, opts.sep());
+    if raw.is_empty() {
+        return match opts.empty_values {
+            EmptyValues::Error => Err(err(
+                ErrorKind::MissingValue,
+                format!("Missing value after `{sep}`. Write a value, or `\"\"`, `[]` or `{{}}` for an empty one."),
+                ln,
+            )),
+            EmptyValues::Null => Ok(ParsedValue::Immediate(Node::Null)),
+            EmptyValues::EmptyString => Ok(ParsedValue::Immediate(Node::Str(String::new()))),
+        };
+    }
     match raw {
         "`true`" => return Ok(ParsedValue::Immediate(Node::Bool(true))),
         "`false`" => return Ok(ParsedValue::Immediate(Node::Bool(false))),
@@ -142,111 +756,561 @@ fn parse_value(raw: &str, ln: Option<usize>) -> Result<ParsedValue, PyErr> {
         "{}" => return Ok(ParsedValue::Immediate(Node::Empty(EmptyObject::EmptyDict))),
         _ => {}
     }
+    if let Some(word) = raw.strip_prefix('`').and_then(|r| r.strip_suffix('`'))
+        && let Some(b) = [true, false].into_iter().find(|b| word.eq_ignore_ascii_case(&b.to_string()))
+    {
+        if opts.case_insensitive_literals {
+            return Ok(ParsedValue::Immediate(Node::Bool(b)));
+        }
+        return Err(err(
+            ErrorKind::InvalidValue,
+            format!("Invalid value: {shown}. Booleans are lower-case: `{b}`."),
+            ln,
+        ));
+    }
+    if raw.starts_with('[') {
+        let list = strip_inline_comment(raw);
+        let Some(inner) = list[1..].strip_suffix(']') else {
+            return Err(err(
+                ErrorKind::InvalidValue,
+                format!("Invalid value: {shown}. The inline list has no closing `]`; it must end on the same line."),
+                ln,
+            ));
+        };
+        return parse_inline_list(list, inner, ln, opts).map(ParsedValue::Immediate);
+    }
+    // Quoted strings come before numbers, so `"42"` stays a string.
+    if raw.len() >= 2 && raw.starts_with('"') && raw.ends_with('"') {
+        return unquote(&raw[1..raw.len() - 1], ln).map(|s| ParsedValue::Immediate(Node::Str(s)));
+    }
 
-    // int?
-    if let Ok(v) = raw.parse::<i64>() {
-        return Ok(ParsedValue::Immediate(Node::Int(v)));
+    let number = raw.strip_prefix('`').and_then(|r| r.strip_suffix('`')).filter(|_| strict_types).unwrap_or(raw);
+    // `01234e0` would become the integer 1234 under `scientific_ints`
+    let exponent_int = number.contains(['e', 'E'])
+        && has_leading_zero(number.split(['e', 'E', '.']).next().unwrap_or(number))
+        && matches!(parse_number(number, opts), Some(Node::Int(_)));
+    if has_leading_zero(number) || exponent_int {
+        return Err(err(
+            ErrorKind::InvalidValue,
+            format!(
+                "Invalid value: {shown}. Integers cannot have leading zeros; remove them, or use a \
+                 string block if the zeros are part of the value."
+            ),
+            ln,
+        ));
     }
-    // float?
-    if let Ok(v) = raw.parse::<f64>() {
-        return Ok(ParsedValue::Immediate(Node::Float(v)));
+    // JSON has no infinity or NaN, so name which one a number would become
+    if let Ok(f) = number.parse::<f64>()
+        && !f.is_finite()
+    {
+        let digits = number.trim_start_matches(['+', '-']).starts_with(|c: char| c.is_ascii_digit() || c == '.');
+        let why = if digits {
+            "The number is too large for a 64-bit float and would become infinity"
+        } else {
+            "STC numbers cannot be infinity or NaN"
+        };
+        return Err(err(
+            ErrorKind::InvalidValue,
+            format!("Invalid value: {shown}. {why}; use a string block to keep the text."),
+            ln,
+        ));
+    }
+    if strict_types {
+        if let Some(inner) = raw.strip_prefix('`').and_then(|r| r.strip_suffix('`')) {
+            if let Some(n) = parse_number(inner, opts) {
+                return Ok(ParsedValue::Immediate(n));
+            }
+        } else if parse_number(raw, &ParseOptions::default()).is_some() {
+            return Err(err(
+                ErrorKind::InvalidValue,
+                format!(
+                    "Invalid value: {shown}. Bare numbers are not coerced in strict mode; write \
+                     `{raw}` in backticks for a number, or use a string block to keep the text."
+                ),
+                ln,
+            ));
+        }
+    } else if let Some(n) = parse_number(raw, opts) {
+        return Ok(ParsedValue::Immediate(n));
+    }
+
+    // string block? The opening delimiter picks the fence character, and an
+    // `r` or `b64` directly before it marks a raw or base64 block.
+    for fence_char in ['`', '~'] {
+        let fence = fence_char.to_string().repeat(3);
+        let (kind, opening) = [("r", BlockKind::Raw), ("b64", BlockKind::Base64)]
+            .into_iter()
+            .find_map(|(prefix, kind)| raw.strip_prefix(prefix).filter(|rest| rest.starts_with(&fence)).map(|rest| (kind, rest)))
+            .unwrap_or((BlockKind::Text, raw));
+        if opening.starts_with(&fence) {
+            let count = opening.chars().take_while(|&c| c == fence_char).count();
+            let rest = &opening[count..];
+            if is_language_tag(rest) {
+                let lang = Some(rest.to_string());
+                return Ok(ParsedValue::StringStart { fence_char, count, lang, kind });
+            }
+            if !rest.is_empty() {
+                return Err(err(
+                    ErrorKind::InvalidValue,
+                    format!(
+                        "Unexpected `{rest}` after the opening fence. String blocks cannot be \
+                         written inline; put the content on the lines following the fence."
+                    ),
+                    ln,
+                ));
+            }
+            return Ok(ParsedValue::StringStart { fence_char, count, lang: None, kind });
+        }
     }
 
-    // string block?
-    if raw.starts_with("```") {
-        let bt_count = raw.chars().take_while(|&c| c == '`').count();
-        return Ok(ParsedValue::StringStart { bt_count });
+    // Common typos get a targeted hint instead of the full list of value forms.
+    if raw == "true" || raw == "false" {
+        return Err(err(
+            ErrorKind::InvalidValue,
+            format!("Invalid value: {shown}. Booleans are written in backticks: `{raw}`."),
+            ln,
+        ));
+    }
+    if looks_like_bare_word(raw) {
+        return Err(err(
+            ErrorKind::InvalidValue,
+            format!(
+                "Invalid value: {shown}. Did you mean a string block? Wrap it in ``` fences:\n\
+                 ```\n{raw}\n```"
+            ),
+            ln,
+        ));
+    }
+    // `time: 12:30` splits at the first colon, leaving the rest of a string.
+    if raw.contains(sep) {
+        return Err(err(
+            ErrorKind::InvalidValue,
+            format!(
+                "Invalid value: {shown}. Only the first `{sep}` separates key and value, so the rest \
+                 looks like a string; wrap it in ``` fences:\n```\n{raw}\n```"
+            ),
+            ln,
+        ));
     }
 
     Err(err(
+        ErrorKind::InvalidValue,
         format!(
-            "Invalid value: {raw}. Value must be:\n\
+            "Invalid value: {shown}. Value must be:\n\
              - `true`, `false` for boolean\n\
              - `[]` for an empty list\n\
              - a number for integer or float\n\
              - a string block enclosed in backticks \
              (`, with the number of backticks larger than \
-             the maximum consecutive number of backticks in the string)."
+             the maximum consecutive number of backticks in the string), \
+             or in tildes (~~~) following the same rule."
         ),
         ln,
     ))
 }
 
-fn fill_in_value(root: &mut Node, path: &[String], value: Node) -> Result<(), PyErr> {
+/// `raw` up to a `#` comment outside quoted strings, trimmed.
+fn strip_inline_comment(raw: &str) -> &str {
+    let (mut in_quote, mut escaped) = (false, false);
+    for (i, c) in raw.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_quote => escaped = true,
+            '"' => in_quote = !in_quote,
+            '#' if !in_quote => return raw[..i].trim_end(),
+            _ => {}
+        }
+    }
+    raw
+}
+
+/// Parse `[1, "two", `true`]`: each item is a single-line value, separated by
+/// commas outside quoted strings. One trailing comma is allowed. Nested lists
+/// are rejected.
+fn parse_inline_list(raw: &str, inner: &str, ln: Option<usize>, opts: &ParseOptions) -> Result<Node, StcError> {
+    let shown = Visible(raw);
+    if inner.trim().is_empty() {
+        return Ok(Node::Empty(EmptyObject::EmptyList));
+    }
+    let mut items = Vec::new();
+    let (mut start, mut in_quote, mut escaped) = (0, false, false);
+    for (i, c) in inner.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_quote => escaped = true,
+            '"' => in_quote = !in_quote,
+            '[' | ']' if !in_quote => {
+                return Err(err(
+                    ErrorKind::InvalidValue,
+                    format!("Invalid value: {shown}. Inline lists cannot be nested; write the items as `key.$N` lines."),
+                    ln,
+                ));
+            }
+            ',' if !in_quote => {
+                items.push(&inner[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    let last = &inner[start..];
+    if !last.trim().is_empty() || items.is_empty() {
+        items.push(last);
+    }
+    if let Some(max) = opts.max_list_len
+        && items.len() > max
+    {
+        return Err(err(
+            ErrorKind::LimitExceeded,
+            format!("Invalid value: {shown}. The inline list has {} items, more than max_list_len={max}.", items.len()),
+            ln,
+        ));
+    }
+    let nodes = items
+        .into_iter()
+        .map(|item| match item.trim() {
+            "" => Err(err(
+                ErrorKind::InvalidValue,
+                format!("Invalid value: {shown}. Inline lists cannot have empty items."),
+                ln,
+            )),
+            item => match parse_value(item, ln, opts)? {
+                ParsedValue::Immediate(n) => Ok(n),
+                ParsedValue::StringStart { .. } => Err(err(
+                    ErrorKind::InvalidValue,
+                    format!("Invalid value: {shown}. String blocks cannot be inline list items."),
+                    ln,
+                )),
+            },
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Node::List(nodes))
+}
+
+/// What a key with nothing after its `:` means.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmptyValues {
+    /// Reject the line (the default).
+    #[default]
+    Error,
+    /// The value is null (`None` in Python).
+    Null,
+    /// The value is `""`.
+    EmptyString,
+}
+
+impl std::str::FromStr for EmptyValues {
+    type Err = StcError;
+
+    fn from_str(s: &str) -> Result<Self, StcError> {
+        match s {
+            "error" => Ok(EmptyValues::Error),
+            "null" => Ok(EmptyValues::Null),
+            "empty_string" => Ok(EmptyValues::EmptyString),
+            _ => Err(StcError::InvalidArgument(format!(
+                "Unknown empty_values policy `{s}`; expected \"error\", \"null\" or \"empty_string\"."
+            ))),
+        }
+    }
+}
+
+/// What to do when the same scalar key is assigned more than once.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateKeys {
+    /// Reject the document (the default).
+    #[default]
+    Error,
+    /// Keep the value from the last assignment.
+    LastWins,
+    /// Keep the value from the first assignment and ignore later ones.
+    FirstWins,
+}
+
+impl std::str::FromStr for DuplicateKeys {
+    type Err = StcError;
+
+    fn from_str(s: &str) -> Result<Self, StcError> {
+        match s {
+            "error" => Ok(DuplicateKeys::Error),
+            "last_wins" => Ok(DuplicateKeys::LastWins),
+            "first_wins" => Ok(DuplicateKeys::FirstWins),
+            _ => Err(StcError::InvalidArgument(format!(
+                "Unknown duplicate_keys policy `{s}`; expected \"error\", \"last_wins\" or \"first_wins\"."
+            ))),
+        }
+    }
+}
+
+fn join_key<S: AsRef<str>>(path: &[S]) -> String {
+    path.iter().map(AsRef::as_ref).collect::<Vec<_>>().join(".")
+}
+
+/// Set `value` at `path`, creating intermediate maps as needed. Segments are
+/// only copied into owned keys when a new map entry is created.
+fn fill_in_value<S: AsRef<str>>(
+    root: &mut Node,
+    path: &[S],
+    value: Node,
+    opts: &ParseOptions,
+    ln: usize,
+    span: Option<Span>,
+) -> Result<(), StcError> {
+    let duplicates = opts.duplicate_keys;
     // Traverse or create maps along the way, then set the final key.
     let mut current = root;
     for (i, piece) in path.iter().enumerate().take(path.len().saturating_sub(1)) {
+        let piece = piece.as_ref();
         // ensure current is a map
-        if matches!(current, Node::Map(_)) == false {
-            let joined = path[..=i].join(".");
-            return Err(err(format!(
+        if !matches!(current, Node::Map(..)) {
+            return Err(err(ErrorKind::KeyConflict, format!(
                 "Key `{}` is set both a value and at least one list item / dict attribute.",
-                joined
-            ), None));
+                join_key(&path[..=i])
+            ), Some(ln)));
         }
         // descend / create
-        let map = current.as_map_mut()?;
-        current = map.entry(piece.clone()).or_insert_with(Node::new_map);
-        if !matches!(current, Node::Map(_)) && i + 1 < path.len() - 1 {
-            let joined = path[..=i].join(".");
-            return Err(err(format!(
-                "Key `{}` is set both a value and at least one list item / dict attribute.",
-                joined
-            ), None));
+        let (map, lines) = current.as_map_mut()?;
+        if !map.contains_key(piece) {
+            map.insert(piece.to_string(), Node::new_map(Some(ln)));
+            lines.note(piece, ln);
+        }
+        current = map.get_mut(piece).expect("entry was just ensured");
+        if matches!(current, Node::List(_)) {
+            return Err(inline_list_conflict(&path[..=i], ln));
+        }
+        // `x: {}` then `x.a: 1` fills the dict in; `[]` likewise takes `$N` items
+        let child_is_index = path[i + 1].as_ref().starts_with('$');
+        if opts.open_empty_collections
+            && let Node::Empty(empty) = current
+            && (*empty == EmptyObject::EmptyList) == child_is_index
+        {
+            let created = lines.values.remove(piece).unwrap_or(ln);
+            lines.spans.remove(piece);
+            *current = Node::new_map(Some(created));
+        }
+        if !matches!(current, Node::Map(..)) {
+            let first = lines.values.get(piece).copied();
+            return Err(value_container_conflict(&path[..=i], first, ln));
         }
     }
     // set the last piece
-    let last = path.last().expect("nonempty path");
-    let map = current.as_map_mut()?;
-    if let Some(existing) = map.get(last) {
+    let last = path.last().expect("nonempty path").as_ref();
+    let (map, lines) = current.as_map_mut()?;
+    if let Some(existing) = map.get_mut(last) {
         match existing {
-            Node::Map(_) => {
-                return Err(err(format!(
-                    "Key `{}` is set both a value directly and at least one list item / dict attribute.",
-                    path.join(".")
-                ), None));
-            }
-            _ => {
-                return Err(err(format!(
-                    "Key `{}` is set at least two values {:?} | {:?}.",
-                    path.join("."), existing_short(existing), existing_short(&value)
-                ), None));
+            Node::Map(..) if matches!(value, Node::List(_)) => return Err(inline_list_conflict(path, ln)),
+            Node::Map(_, child) => {
+                let first = child.created.map_or_else(String::new, |c| {
+                    format!(": first set as a container on line {c}; attempted as a value on line {ln}")
+                });
+                return Err(err(ErrorKind::KeyConflict, format!(
+                    "Key `{}` is set both a value directly and at least one list item / dict attribute{first}.",
+                    join_key(path)
+                ), Some(ln)));
             }
+            // Scalar-vs-scalar conflicts are the only ones a policy can resolve.
+            _ => match duplicates {
+                DuplicateKeys::Error if same_scalar(existing, &value) => {
+                    return Err(err(ErrorKind::DuplicateValue, format!(
+                        "Key `{}` assigned the same value twice; remove the duplicate line.",
+                        join_key(path)
+                    ), Some(ln)));
+                }
+                DuplicateKeys::Error => {
+                    return Err(err(ErrorKind::DuplicateValue, format!(
+                        "Key `{}` is set at least two values {:?} | {:?}.",
+                        join_key(path), existing_short(existing), existing_short(&value)
+                    ), Some(ln)));
+                }
+                DuplicateKeys::FirstWins => return Ok(()),
+                DuplicateKeys::LastWins => {
+                    *existing = value;
+                    if let Some(span) = span {
+                        lines.spans.insert(last.to_string(), span);
+                    }
+                    return Ok(());
+                }
+            },
         }
     }
-    map.insert(last.clone(), value);
+    map.insert(last.to_string(), value);
+    lines.note(last, ln);
+    lines.values.insert(last.to_string(), ln);
+    if let Some(span) = span {
+        lines.spans.insert(last.to_string(), span);
+    }
     Ok(())
 }
 
+/// A key set as a value on line `first` and then used as a container on `ln`.
+fn value_container_conflict<S: AsRef<str>>(path: &[S], first: Option<usize>, ln: usize) -> StcError {
+    let first = first.map_or_else(String::new, |f| {
+        format!(": first set as a value on line {f}; attempted as a container on line {ln}")
+    });
+    err(
+        ErrorKind::KeyConflict,
+        format!("Key `{}` is set both a value and at least one list item / dict attribute{first}.", join_key(path)),
+        Some(ln),
+    )
+}
+
+fn inline_list_conflict<S: AsRef<str>>(path: &[S], ln: usize) -> StcError {
+    err(
+        ErrorKind::KeyConflict,
+        format!("Key `{}` is set both as an inline list and with `$N` items or dict attributes.", join_key(path)),
+        Some(ln),
+    )
+}
+
+/// Whether two leaf nodes hold the same value, so a repeated line is merely
+/// redundant rather than contradictory.
+fn same_scalar(a: &Node, b: &Node) -> bool {
+    match (a, b) {
+        (Node::Bool(x), Node::Bool(y)) => x == y,
+        (Node::Int(x), Node::Int(y)) => x == y,
+        (Node::Float(x), Node::Float(y)) => x == y,
+        (Node::Lexeme(x), Node::Lexeme(y)) | (Node::Str(x), Node::Str(y)) => x == y,
+        (Node::Bytes(x), Node::Bytes(y)) => x == y,
+        (Node::Empty(x), Node::Empty(y)) => x == y,
+        (Node::Null, Node::Null) => true,
+        (Node::List(x), Node::List(y)) => x.len() == y.len() && x.iter().zip(y).all(|(a, b)| same_scalar(a, b)),
+        _ => false,
+    }
+}
+
 fn existing_short(n: &Node) -> String {
     match n {
-        Node::Map(_) => "Map".into(),
+        Node::Map(..) => "Map".into(),
         Node::Bool(b) => format!("Bool({b})"),
         Node::Int(i) => format!("Int({i})"),
         Node::Float(f) => format!("Float({f})"),
+        Node::Lexeme(s) => format!("Number({s})"),
         Node::Str(s) => format!("Str({:?})", s),
         Node::Empty(EmptyObject::EmptyList) => "EmptyList".into(),
         Node::Empty(EmptyObject::EmptyDict) => "EmptyDict".into(),
+        Node::Null => "Null".into(),
+        Node::Bytes(b) => format!("Bytes({} bytes)", b.len()),
+        Node::List(items) => format!("List({})", items.iter().map(existing_short).collect::<Vec<_>>().join(", ")),
+    }
+}
+
+/// Collects the `MapLines::spans` of a tree while it is finalized, keyed by
+/// JSON pointer (`/servers/0/host`).
+#[derive(Default)]
+struct SpanSink {
+    /// Pointer of the node being finalized.
+    pointer: String,
+    spans: BTreeMap<String, Span>,
+}
+
+impl SpanSink {
+    /// Run `f` with the pointer extended by `segment`, recording `span` there.
+    fn child<T>(&mut self, segment: &str, span: Option<&Span>, f: impl FnOnce(&mut Self) -> T) -> T {
+        let len = self.pointer.len();
+        self.pointer.push('/');
+        self.pointer.push_str(&segment.replace('~', "~0").replace('/', "~1"));
+        if let Some(span) = span {
+            self.spans.insert(self.pointer.clone(), *span);
+        }
+        let out = f(self);
+        self.pointer.truncate(len);
+        out
     }
 }
 
-fn finalize_node(n: Node, prefix: &str) -> Result<Value, PyErr> {
+fn finalize_node(n: Node, prefix: &str, max_list_len: Option<usize>, spans: Option<&mut SpanSink>) -> Result<Value, StcError> {
     match n {
         Node::Bool(b) => Ok(Value::Bool(b)),
         Node::Int(i) => Ok(Value::Number(Number::from(i))),
         Node::Float(f) => {
             Number::from_f64(f)
                 .map(Value::Number)
-                .ok_or_else(|| err("Invalid float value (NaN/inf) not representable in JSON", None))
+                .ok_or_else(|| err(ErrorKind::InvalidValue, "Invalid float value (NaN/inf) not representable in JSON", None))
         }
         Node::Str(s) => Ok(Value::String(s)),
+        Node::Lexeme(s) => Ok(lexeme::marker(s)),
         Node::Empty(EmptyObject::EmptyList) => Ok(Value::Array(vec![])),
         Node::Empty(EmptyObject::EmptyDict) => Ok(Value::Object(Map::new())),
-        Node::Map(m) => finalize_map(m, prefix),
+        Node::Null => Ok(Value::Null),
+        Node::Bytes(b) => Ok(base64::marker(&b)),
+        Node::List(items) => items
+            .into_iter()
+            .enumerate()
+            .map(|(i, n)| finalize_node(n, &join_key(&[prefix, &format!("${i}")]), max_list_len, None))
+            .collect::<Result<_, _>>()
+            .map(Value::Array),
+        Node::Map(m, lines) => finalize_map(m, lines, prefix, max_list_len, spans),
     }
 }
 
-fn finalize_map(mut d: HashMap<String, Node>, prefix: &str) -> Result<Value, PyErr> {
+fn list_dict_conflict(here: &str, lines: &MapLines) -> StcError {
+    let at = |l: Option<usize>| l.map_or_else(|| "an unknown line".to_string(), |l| format!("line {l}"));
+    err(
+        ErrorKind::KeyConflict,
+        format!(
+            "{here} is set both as a list and a dict: first `$N` index on {}, first named key on {}.",
+            at(lines.first_index),
+            at(lines.first_key)
+        ),
+        lines.created,
+    )
+}
+
+/// Check the `$N` keys of a map that is a list and return `(index, key)` for
+/// each item in index order. `keys` must be sorted.
+fn list_slots(keys: &[String], lines: &MapLines, here: &str, max_list_len: Option<usize>) -> Result<Vec<(usize, String)>, StcError> {
+    let ln = lines.created;
+    if keys.iter().any(|k| !k.starts_with('$')) {
+        return Err(list_dict_conflict(here, lines));
+    }
+    // Negative indices count from the end of the final list.
+    let len = keys.len();
+    if let Some(max) = max_list_len
+        && len > max
+    {
+        return Err(err(
+            ErrorKind::LimitExceeded,
+            format!("List `{here}` has {len} items, more than max_list_len={max}."),
+            ln,
+        ));
+    }
+    let mut slots: Vec<(usize, String)> = Vec::with_capacity(len);
+    for k in keys {
+        let raw: i64 = k[1..].parse().map_err(|_| err(ErrorKind::InvalidIndex, format!("{here} has invalid list index `{k}`."), ln))?;
+        let idx = if raw < 0 { len as i64 + raw } else { raw };
+        if idx < 0 {
+            return Err(err(ErrorKind::InvalidIndex, format!("{here} has index `{k}`, which is out of range for a list of {len} items."), ln));
+        }
+        slots.push((idx as usize, k.clone()));
+    }
+    slots.sort();
+    if let Some(w) = slots.windows(2).find(|w| w[0].0 == w[1].0) {
+        return Err(err(
+            ErrorKind::InvalidIndex,
+            format!("{here} sets index ${} twice: `{}` and `{}` refer to the same item.", w[0].0, w[0].1, w[1].1),
+            ln,
+        ));
+    }
+    // Slots are sorted and distinct, so the first gap is the first missing index.
+    if let Some(missing) = slots.iter().enumerate().find(|(i, (idx, _))| i != idx).map(|(i, _)| i) {
+        return Err(err(
+            ErrorKind::InvalidIndex,
+            format!("List `{here}` is missing index {missing}; indices 0..{} must all be present.", len - 1),
+            ln,
+        ));
+    }
+    Ok(slots)
+}
+
+/// Turn a map into a list or dict. Structural errors point at the line that
+/// first created the map; a list/dict conflict also names the first line of
+/// each kind of entry.
+fn finalize_map(
+    mut d: HashMap<String, Node>,
+    lines: MapLines,
+    prefix: &str,
+    max_list_len: Option<usize>,
+    mut spans: Option<&mut SpanSink>,
+) -> Result<Value, StcError> {
     if d.is_empty() {
         return Ok(Value::Object(Map::new()));
     }
@@ -257,138 +1321,1151 @@ fn finalize_map(mut d: HashMap<String, Node>, prefix: &str) -> Result<Value, PyE
     let is_list = keys.first().map(|k| k.starts_with('$')).unwrap_or(false);
 
     if is_list {
-        if keys.iter().any(|k| !k.starts_with('$')) {
-            return Err(err(format!("{here} is set both as a list and a dict."), None));
-        }
-        let mut indices = Vec::with_capacity(keys.len());
-        for k in &keys {
-            let idx: usize = k[1..].parse().map_err(|_| err(format!("{here} has invalid list index `{k}`."), None))?;
-            indices.push(idx);
-        }
-        if indices.iter().min() != Some(&0) || indices.iter().max() != Some(&(indices.len() - 1)) {
-            return Err(err(format!("{here} is set as a list, but not all indices 0..{} are present.", indices.len()-1), None));
-        }
-        let mut arr = vec![Value::Null; indices.len()];
-        for k in keys {
-            let idx: usize = k[1..].parse().map_err(|_| err(format!("{here} has invalid list index `{k}`."), None))?;
+        let slots = list_slots(&keys, &lines, &here, max_list_len)?;
+        let len = slots.len();
+        // Push the items in index order, so no placeholder value ever exists.
+        let mut arr = Vec::with_capacity(len);
+        for (idx, k) in slots {
+            debug_assert_eq!(idx, arr.len(), "slots are checked to be contiguous");
             let child = d.remove(&k).ok_or_else(|| {
                 err(
+                    ErrorKind::Internal,
                     format!("Internal error: key `{k}` missing while finalizing list at {here}."),
                     None,
                 )
             })?;
             let next_prefix = if prefix.is_empty() { k.clone() } else { format!("{prefix}.{k}") };
-            arr[idx] = finalize_node(child, &next_prefix)?;
+            let value = match spans.as_deref_mut() {
+                Some(sink) => sink.child(&idx.to_string(), lines.spans.get(&k), |sink| {
+                    finalize_node(child, &next_prefix, max_list_len, Some(sink))
+                }),
+                None => finalize_node(child, &next_prefix, max_list_len, None),
+            };
+            arr.push(value?);
         }
         Ok(Value::Array(arr))
     } else {
         if keys.iter().any(|k| k.starts_with('$')) {
-            return Err(err(format!("{here} is set both as a list and a dict."), None));
+            return Err(list_dict_conflict(&here, &lines));
         }
         let mut obj = Map::new();
         for k in keys {
             let child = d.remove(&k).ok_or_else(|| {
                 err(
+                    ErrorKind::Internal,
                     format!("Internal error: key `{k}` missing while finalizing dict at {here}."),
                     None,
                 )
             })?;
             let next_prefix = if prefix.is_empty() { k.clone() } else { format!("{prefix}.{k}") };
-            obj.insert(k, finalize_node(child, &next_prefix)?);
+            let key = decode_segment(&k).into_owned();
+            let value = match spans.as_deref_mut() {
+                Some(sink) => sink.child(&key, lines.spans.get(&k), |sink| {
+                    finalize_node(child, &next_prefix, max_list_len, Some(sink))
+                }),
+                None => finalize_node(child, &next_prefix, max_list_len, None),
+            };
+            obj.insert(key, value?);
         }
         Ok(Value::Object(obj))
     }
 }
 
-/// Parse STC from &str into serde_json::Value
-pub fn parse_stc(input: &str) -> Result<Value, PyErr> {
-    if input.trim() == "{}" {
-        return Ok(Value::Object(Map::new()));
+/// Strip the common leading whitespace of all non-blank lines. Whitespace-only
+/// lines are emptied, matching `textwrap.dedent`.
+fn dedent(s: &str) -> String {
+    let mut margin: Option<&str> = None;
+    for line in s.split('\n').filter(|l| !l.trim().is_empty()) {
+        let indent = &line[..line.len() - line.trim_start().len()];
+        margin = Some(match margin {
+            None => indent,
+            Some(m) => {
+                let common = m.char_indices().zip(indent.chars())
+                    .find(|((_, a), b)| a != b)
+                    .map_or(m.len().min(indent.len()), |((i, _), _)| i);
+                &m[..common]
+            }
+        });
     }
+    let margin = margin.unwrap_or("");
+    s.split('\n')
+        .map(|l| if l.trim().is_empty() { "" } else { &l[margin.len()..] })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
 
-    let mut root = Node::new_map();
+/// Options accepted by `parse_stc_with`. `Default` gives the strict behavior of `parse_stc`.
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Keep the final `\n` of string blocks, and allow an empty block to mean `""`.
+    pub keep_trailing_newline: bool,
+    /// Remove the longest common leading whitespace from string block lines,
+    /// like Python's `textwrap.dedent`.
+    pub dedent: bool,
+    /// Require each list's `$N` indices to appear in the source in order: `$0`
+    /// first, then each index equal to the previous one or one more.
+    pub require_ordered_indices: bool,
+    /// How repeated assignments to the same scalar key are resolved.
+    pub duplicate_keys: DuplicateKeys,
+    /// Validate string blocks whose fence declares a known language (`json`).
+    pub validate_block_content: bool,
+    /// Keep the source text of numbers that `to_string` would otherwise write
+    /// differently (`1.50`, `+5`, `1e3`), so `to_json_with` and the Python
+    /// bindings can write it back verbatim. The `Value`s the parse functions
+    /// return hold the plain number.
+    pub preserve_number_lexemes: bool,
+    /// Only type numbers written in backticks (`` `42` ``); a bare number such
+    /// as `01234` is an error instead of being coerced. This is Python's
+    /// `coerce_numbers=False`, which shows up in `Debug` output
+    /// and cache keys as `strict_types: true`.
+    pub strict_types: bool,
+    /// Keep the source text of every float, so it can be rebuilt exactly
+    /// (Python's `use_decimal`) or written back verbatim by `to_json_with`.
+    /// Like `preserve_number_lexemes`, it does not change the returned `Value`s.
+    pub keep_float_text: bool,
+    /// Reject input longer than this many bytes, counting `\n` separators.
+    pub max_bytes: Option<usize>,
+    /// Reject input with more than this many lines. A final `\n` does not
+    /// start a new line.
+    pub max_lines: Option<usize>,
+    /// Right-trim each string block content line. Off by default, so blocks
+    /// are captured losslessly.
+    pub strip_trailing_whitespace_in_blocks: bool,
+    /// Reject any list with more than this many items.
+    pub max_list_len: Option<usize>,
+    /// Append the offending source line and a caret under the error's column
+    /// to parse error messages.
+    pub pretty_errors: bool,
+    /// Split keys from values at the first occurrence of this string instead
+    /// of `:`, e.g. `=` or `=>`.
+    pub separator: Option<String>,
+    /// Type scientific notation with an integral value that fits in i64
+    /// (`1e3`, `1.5e2`) as an int instead of a float.
+    pub scientific_ints: bool,
+    /// Type every float with an integral value that fits in i64 (`2.0`,
+    /// `1.5e2`) as an int; `2.5` stays a float. This covers the cases
+    /// `scientific_ints` does, so the two need not be combined. With
+    /// `preserve_number_lexemes` such a number keeps its text (`2.0`) on the
+    /// int; `keep_float_text` leaves it alone, as it is no longer a float.
+    pub float_to_int_when_whole: bool,
+    /// What `key:` with no value means.
+    pub empty_values: EmptyValues,
+    /// Let a string block's closing fence be indented. By default the fence
+    /// must start the line, so an indented content line that looks like the
+    /// fence stays content; trailing whitespace, including a `\r`, is always
+    /// allowed. Useful together with `dedent`.
+    pub allow_indented_fence: bool,
+    /// Match the backticked literals case-insensitively, so `` `True` `` and
+    /// `` `FALSE` `` are booleans.
+    pub case_insensitive_literals: bool,
+    /// STC key paths (`server.port`, `items.$0`) the document must contain.
+    /// Every missing one is listed in a single error.
+    pub required: Vec<String>,
+    /// Resolve `key: !include path` values when parsing a file with
+    /// `parse_file`. Off by default, so untrusted input cannot read files.
+    pub allow_includes: bool,
+    /// Let a `key: {}` or `key: []` be filled in by later lines (`key.a: 1`,
+    /// `key.$0: 1`) instead of being a finished value they conflict with.
+    pub open_empty_collections: bool,
+    /// Split the input into lines at this string instead of `\n`, e.g. `;`
+    /// to write `a: 1; b: 2` as one line. Line numbers in errors count these
+    /// lines. The input is split before anything else, so not even a quoted
+    /// string may contain the separator. String blocks need real lines, so
+    /// they are rejected unless the separator is `\n`.
+    pub line_separator: Option<String>,
+}
 
-    let mut in_string = false;
-    let mut string_bt_count: usize = 0;
-    let mut string_path: Vec<String> = Vec::new();
-    let mut string_buf = String::new();
+/// Fluent setters, one per field, in the style of `std::fs::OpenOptions`:
+///
+/// ```
+/// use stc_rust::{parse_stc_with, DuplicateKeys, ParseOptions};
+///
+/// let opts = ParseOptions::new().separator("=").duplicate_keys(DuplicateKeys::LastWins);
+/// assert_eq!(parse_stc_with("a = 1\na = 2", &opts)?, serde_json::json!({"a": 2}));
+/// # Ok::<(), stc_rust::StcError>(())
+/// ```
+impl ParseOptions {
+    /// The default options, as used by `parse_stc`.
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-    for (idx, raw_line) in input.split('\n').enumerate() {
-        let ln = idx + 1;
+    /// The key/value separator, `:` unless `separator` is set.
+    fn sep(&self) -> &str {
+        self.separator.as_deref().unwrap_or(":")
+    }
 
-        if !in_string {
-            if raw_line.trim().is_empty() {
-                continue;
+    /// The line separator, `\n` unless `line_separator` is set.
+    fn line_sep(&self) -> &str {
+        self.line_separator.as_deref().unwrap_or("\n")
+    }
+
+    /// Reject options no document could be parsed with, such as an empty
+    /// separator, which would otherwise surface as confusing key errors.
+    fn check(&self) -> Result<(), StcError> {
+        for (name, sep) in [("separator", &self.separator), ("line_separator", &self.line_separator)] {
+            if sep.as_deref() == Some("") {
+                return Err(StcError::InvalidArgument(format!("`{name}` must not be empty.")));
+            }
+        }
+        Ok(())
+    }
+
+    /// The lines of `input`, split at `line_sep`.
+    pub(crate) fn lines<'a>(&'a self, input: &'a str) -> std::str::Split<'a, &'a str> {
+        input.split(self.line_sep())
+    }
+
+    pub fn keep_trailing_newline(mut self, yes: bool) -> Self {
+        self.keep_trailing_newline = yes;
+        self
+    }
+
+    pub fn dedent(mut self, yes: bool) -> Self {
+        self.dedent = yes;
+        self
+    }
+
+    pub fn require_ordered_indices(mut self, yes: bool) -> Self {
+        self.require_ordered_indices = yes;
+        self
+    }
+
+    pub fn duplicate_keys(mut self, policy: DuplicateKeys) -> Self {
+        self.duplicate_keys = policy;
+        self
+    }
+
+    pub fn validate_block_content(mut self, yes: bool) -> Self {
+        self.validate_block_content = yes;
+        self
+    }
+
+    pub fn preserve_number_lexemes(mut self, yes: bool) -> Self {
+        self.preserve_number_lexemes = yes;
+        self
+    }
+
+    pub fn strict_types(mut self, yes: bool) -> Self {
+        self.strict_types = yes;
+        self
+    }
+
+    pub fn keep_float_text(mut self, yes: bool) -> Self {
+        self.keep_float_text = yes;
+        self
+    }
+
+    pub fn max_bytes(mut self, max: usize) -> Self {
+        self.max_bytes = Some(max);
+        self
+    }
+
+    pub fn max_lines(mut self, max: usize) -> Self {
+        self.max_lines = Some(max);
+        self
+    }
+
+    pub fn strip_trailing_whitespace_in_blocks(mut self, yes: bool) -> Self {
+        self.strip_trailing_whitespace_in_blocks = yes;
+        self
+    }
+
+    pub fn max_list_len(mut self, max: usize) -> Self {
+        self.max_list_len = Some(max);
+        self
+    }
+
+    pub fn pretty_errors(mut self, yes: bool) -> Self {
+        self.pretty_errors = yes;
+        self
+    }
+
+    pub fn separator(mut self, sep: impl Into<String>) -> Self {
+        self.separator = Some(sep.into());
+        self
+    }
+
+    pub fn scientific_ints(mut self, yes: bool) -> Self {
+        self.scientific_ints = yes;
+        self
+    }
+
+    pub fn float_to_int_when_whole(mut self, yes: bool) -> Self {
+        self.float_to_int_when_whole = yes;
+        self
+    }
+
+    pub fn empty_values(mut self, policy: EmptyValues) -> Self {
+        self.empty_values = policy;
+        self
+    }
+
+    pub fn allow_indented_fence(mut self, yes: bool) -> Self {
+        self.allow_indented_fence = yes;
+        self
+    }
+
+    pub fn case_insensitive_literals(mut self, yes: bool) -> Self {
+        self.case_insensitive_literals = yes;
+        self
+    }
+
+    pub fn required<S: Into<String>>(mut self, paths: impl IntoIterator<Item = S>) -> Self {
+        self.required = paths.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn allow_includes(mut self, yes: bool) -> Self {
+        self.allow_includes = yes;
+        self
+    }
+
+    pub fn open_empty_collections(mut self, yes: bool) -> Self {
+        self.open_empty_collections = yes;
+        self
+    }
+
+    pub fn line_separator(mut self, sep: impl Into<String>) -> Self {
+        self.line_separator = Some(sep.into());
+        self
+    }
+}
+
+/// Incremental STC parser, fed one line at a time.
+///
+/// `parse_stc` is this parser run over every line of a string; callers reading
+/// a large file can feed lines as they arrive instead of holding the whole
+/// input in memory:
+///
+/// ```
+/// let mut p = stc_rust::StcParser::new();
+/// for line in ["a: ```", "hi", "```"] {
+///     p.feed_line(line)?;
+/// }
+/// assert_eq!(p.finish()?, serde_json::json!({"a": "hi"}));
+/// # Ok::<(), stc_rust::StcError>(())
+/// ```
+pub struct StcParser {
+    opts: ParseOptions,
+    root: Node,
+    ln: usize,
+    // input bytes fed so far, for `max_bytes`, counting included files
+    bytes: usize,
+    // lines of other files of the document (those including this one, and
+    // those it includes), so `max_lines` counts the whole document
+    other_lines: usize,
+    last_line_empty: bool,
+    // line number and text of a bare `{}` or `[]` line, only valid as the
+    // whole document
+    root_empty_at: Option<(usize, &'static str)>,
+    seen_content: bool,
+    // last `$N` seen per list prefix, for `require_ordered_indices`
+    last_index: HashMap<String, usize>,
+    // next index per list prefix written with auto-index `$` segments
+    auto_index: HashMap<String, usize>,
+    // list prefixes written with explicit `$N` indices
+    explicit_index: HashSet<String>,
+    // line each scalar list item was set on, to name both lines of a repeat
+    item_lines: HashMap<String, usize>,
+    // files being parsed, for resolving and cycle-checking `!include`
+    includes: include::Includes,
+    // keep numbers as lexeme markers for `preserve_number_lexemes` and
+    // `keep_float_text`; only the Python bindings and JSON output do, so the
+    // public parse functions return plain numbers
+    keep_lexemes: bool,
+    // top-level key of the last value filled in, for `StcReader` to tell
+    // which entries are complete
+    #[cfg_attr(not(feature = "python"), allow(dead_code))]
+    top_key: Option<String>,
+    // key paths written as typed empties (`[]int`), with their element type
+    empty_hints: Vec<(Vec<String>, KeyType)>,
+    // non-fatal key conflicts, when collected by `parse_stc_with_warnings`
+    warnings: Option<Vec<Warning>>,
+    // values committed since the last line, when visited by `parse_stc_visit`
+    commits: Option<Vec<(Vec<String>, Value)>>,
+    // whether to record where each value is written, for `parse_with_spans`,
+    // and the span of the value about to be filled in
+    record_spans: bool,
+    value_span: Option<Span>,
+
+    in_string: bool,
+    // closing fence of the open block, built once when it opens
+    string_fence: String,
+    string_lang: Option<String>,
+    string_kind: BlockKind,
+    string_start_ln: usize,
+    string_path: Vec<String>,
+    string_buf: String,
+}
+
+impl Default for StcParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StcParser {
+    /// A parser with default options.
+    pub fn new() -> Self {
+        Self::with_options(ParseOptions::default())
+    }
+
+    pub fn with_options(opts: ParseOptions) -> Self {
+        StcParser {
+            opts,
+            root: Node::new_map(None),
+            ln: 0,
+            bytes: 0,
+            other_lines: 0,
+            last_line_empty: false,
+            root_empty_at: None,
+            seen_content: false,
+            last_index: HashMap::new(),
+            auto_index: HashMap::new(),
+            explicit_index: HashSet::new(),
+            item_lines: HashMap::new(),
+            includes: include::Includes::default(),
+            keep_lexemes: false,
+            top_key: None,
+            empty_hints: Vec::new(),
+            warnings: None,
+            commits: None,
+            record_spans: false,
+            value_span: None,
+            in_string: false,
+            string_fence: String::new(),
+            string_lang: None,
+            string_kind: BlockKind::Text,
+            string_start_ln: 0,
+            string_path: Vec::new(),
+            string_buf: String::new(),
+        }
+    }
+
+    /// A parser whose values keep preserved numbers as lexeme markers.
+    pub(crate) fn keeping_lexemes(opts: ParseOptions) -> Self {
+        StcParser { keep_lexemes: true, ..Self::with_options(opts) }
+    }
+
+
+    /// Feed one physical line, without its trailing `\n`. Errors are reported
+    /// as soon as the offending line is fed.
+    pub fn feed_line(&mut self, raw_line: &str) -> Result<(), StcError> {
+        let res = self.feed(raw_line);
+        if self.opts.pretty_errors { res.map_err(|e| e.with_snippet(raw_line, self.ln)) } else { res }
+    }
+
+    fn feed(&mut self, raw_line: &str) -> Result<(), StcError> {
+        if self.ln == 0 {
+            self.opts.check()?;
+        }
+        self.ln += 1;
+        self.check_limits(raw_line)?;
+        self.last_line_empty = raw_line.is_empty();
+        let ln = self.ln;
+        // a byte order mark may start the input, but nowhere else
+        let raw_line = if ln == 1 { raw_line.strip_prefix('\u{feff}').unwrap_or(raw_line) } else { raw_line };
+
+        if !self.in_string {
+            // Blank lines and `#` comment lines carry no content, so they may also
+            // surround a bare `{}` or `[]`.
+            if raw_line.trim().is_empty() || raw_line.trim_start().starts_with(COMMENT) {
+                return Ok(());
+            }
+            // A bare `{}` or `[]` is the empty document, so it must be the only content.
+            if let Some((at, empty)) = self.root_empty_at {
+                let sep = self.opts.sep();
+                return Err(err(ErrorKind::MissingColon, format!("Missing `{sep}`. Line content:\n {empty}"), Some(at)));
+            }
+            if !self.seen_content && matches!(raw_line.trim(), "{}" | "[]") {
+                self.root_empty_at = Some((ln, if raw_line.trim() == "{}" { "{}" } else { "[]" }));
+                return Ok(());
             }
-            let Some(colon_idx) = raw_line.find(':') else {
+            self.seen_content = true;
+            let sep = self.opts.sep();
+            let Some(sep_idx) = find_separator(raw_line, sep) else {
                 return Err(err(
-                    format!("Line {ln} missing `:`. Line content:\n {raw_line}"),
-                    None,
+                    ErrorKind::MissingColon,
+                    format!("Missing `{sep}`. Line content:\n {raw_line}"),
+                    Some(ln),
                 ));
             };
-            let (k, v) = raw_line.split_at(colon_idx);
+            let (k, v) = raw_line.split_at(sep_idx);
+            let v = &v[sep.len()..];
             let key = k.trim();
-            let value = v[1..].trim().to_string(); // skip ':'
+            let value = v.trim().to_string();
 
-            let key_path = parse_key(key, Some(ln))?;
-            match parse_value(&value, Some(ln))? {
+            let (key, annotation) = split_annotation(key, Some(ln))?;
+            // `key` and the value are slices of `raw_line`; errors point at them by column
+            let col_of = |part: &str| raw_line[..part.as_ptr() as usize - raw_line.as_ptr() as usize].chars().count();
+            let key_col = col_of(key);
+            let value_col = col_of(v.trim_start()) + 1;
+            let segments = parse_key(key, Some(ln)).map_err(|e| e.shift_column(key_col))?;
+            let key_path = self.resolve_auto_indices(&segments, ln)?;
+            if self.opts.require_ordered_indices {
+                self.check_index_order(&key_path, ln)?;
+            }
+            if self.record_spans {
+                // a string block's span is completed by its closing fence
+                let end_col = value_col + value.chars().count();
+                self.value_span = Some(Span { line: ln, col: value_col, end_line: ln, end_col });
+            }
+            if let Some(target) = value
+                .strip_prefix(include::INCLUDE_PREFIX)
+                .filter(|t| t.is_empty() || t.starts_with(char::is_whitespace))
+            {
+                let (node, (lines, bytes)) =
+                    include::resolve(target.trim(), self, ln).map_err(|e| e.at_column(value_col))?;
+                self.other_lines = lines - self.ln;
+                self.bytes = bytes;
+                return self.fill(&key_path, node, ln);
+            }
+            let (value, hint) = match typed_empty(&value, Some(ln)).map_err(|e| e.at_column(value_col))? {
+                Some((empty, ty)) => (empty.to_string(), Some(ty)),
+                None => (value, None),
+            };
+            let parsed =
+                parse_value(&value, Some(ln), &self.opts).map_err(|e| e.at_column(value_col))?;
+            if let Some(ty) = annotation {
+                check_annotation(key, ty, &parsed, Some(ln)).map_err(|e| e.at_column(value_col))?;
+            }
+            match parsed {
                 ParsedValue::Immediate(n) => {
-                    fill_in_value(&mut root, &key_path, n)?;
+                    let keep = self.keep_lexemes
+                        && ((self.opts.preserve_number_lexemes && lexeme::needs_preserving(&value, &n))
+                            || (self.opts.keep_float_text && matches!(n, Node::Float(_))));
+                    let n = if keep {
+                        Node::Lexeme(value)
+                    } else {
+                        n
+                    };
+                    self.fill(&key_path, n, ln)?;
+                    if let Some(ty) = hint {
+                        self.empty_hints.push((key_path, ty));
+                    }
+                }
+                ParsedValue::StringStart { .. } if self.opts.line_sep() != "\n" => {
+                    return Err(err(
+                        ErrorKind::InvalidValue,
+                        format!(
+                            "Invalid value: {value}. String blocks need newline-separated lines, so they cannot be used with line_separator={:?}.",
+                            self.opts.line_sep()
+                        ),
+                        Some(ln),
+                    )
+                    .at_column(value_col));
                 }
-                ParsedValue::StringStart { bt_count } => {
-                    in_string = true;
-                    string_bt_count = bt_count;
-                    string_path = key_path;
-                    string_buf.clear();
+                ParsedValue::StringStart { fence_char, count, lang, kind } => {
+                    self.in_string = true;
+                    self.string_kind = kind;
+                    self.string_fence = fence_char.to_string().repeat(count);
+                    self.string_lang = lang;
+                    self.string_start_ln = ln;
+                    self.string_path = key_path;
+                    self.string_buf.clear();
                     // The immediate newline after opening fence is trimmed by design:
                     // we *start collecting from the next physical line* (which we do below).
                 }
             }
         } else {
-            // inside a string block
-            let fence: String = std::iter::repeat('`').take(string_bt_count).collect();
-            if raw_line.trim_end() == fence {
-                if string_buf.is_empty() {
-                    return Err(err(
-                        "Empty string block should be formatted as `key: ```\\n\\n```, not ```\\n```.",
-                        Some(ln),
-                    ));
+            // inside a string block; the fence closes it only at the start of
+            // the line unless `allow_indented_fence` is set
+            let fence_line = if self.opts.allow_indented_fence { raw_line.trim() } else { raw_line.trim_end() };
+            if fence_line == self.string_fence {
+                if let Some(span) = &mut self.value_span {
+                    span.end_line = ln;
+                    span.end_col = raw_line.trim_end().chars().count() + 1;
                 }
-                // drop the final '\n'
-                if string_buf.ends_with('\n') {
-                    string_buf.pop();
+                if self.string_kind == BlockKind::Base64 {
+                    let Some(data) = base64::decode(&self.string_buf) else {
+                        return Err(err(
+                            ErrorKind::InvalidValue,
+                            format!("Invalid base64 in the block starting at line {}.", self.string_start_ln),
+                            Some(self.string_start_ln),
+                        ));
+                    };
+                    let path = std::mem::take(&mut self.string_path);
+                    self.fill(&path, Node::Bytes(data), self.string_start_ln)?;
+                    self.in_string = false;
+                    self.string_fence.clear();
+                    self.string_buf.clear();
+                    return Ok(());
                 }
-                let s = std::mem::take(&mut string_buf);
-                fill_in_value(&mut root, &string_path, Node::Str(s))?;
-                in_string = false;
-                string_path.clear();
-                string_bt_count = 0;
+                let s = if self.string_kind == BlockKind::Raw {
+                    // Raw blocks keep every content line and its `\n` as written.
+                    std::mem::take(&mut self.string_buf)
+                } else {
+                    if self.string_buf.is_empty() && !self.opts.keep_trailing_newline {
+                        return Err(err(
+                            ErrorKind::InvalidValue,
+                            "Empty string block should be formatted as `key: ```\\n\\n```, not ```\\n```.",
+                            Some(ln),
+                        ));
+                    }
+                    // drop the final '\n'
+                    if self.string_buf.ends_with('\n') && !self.opts.keep_trailing_newline {
+                        self.string_buf.pop();
+                    }
+                    let s = std::mem::take(&mut self.string_buf);
+                    if self.opts.dedent { dedent(&s) } else { s }
+                };
+                if let (true, Some(lang)) = (self.opts.validate_block_content, &self.string_lang) {
+                    validate_block(lang, &s, self.string_start_ln)?;
+                }
+                let path = std::mem::take(&mut self.string_path);
+                self.fill(&path, Node::Str(s), self.string_start_ln)?;
+                self.in_string = false;
+                self.string_fence.clear();
             } else {
                 // accumulate with the line + '\n'
-                string_buf.push_str(raw_line);
-                string_buf.push('\n');
+                let strip = self.opts.strip_trailing_whitespace_in_blocks && self.string_kind != BlockKind::Raw;
+                let content = if strip { raw_line.trim_end() } else { raw_line };
+                self.string_buf.push_str(content);
+                self.string_buf.push('\n');
             }
         }
+        Ok(())
     }
 
-    if in_string {
-        return Err(err(
-            format!("Unclosed string block starting at line {}.", input.lines().count()),
+    /// Enforce `max_bytes` / `max_lines` before `raw_line` is parsed, so
+    /// oversized input is rejected without building anything from it.
+    /// Lines of the document fed so far, across all its files. A final empty
+    /// line, the one after a trailing line separator, is not counted.
+    fn lines_used(&self) -> usize {
+        self.other_lines + self.ln - usize::from(self.last_line_empty)
+    }
+
+    fn check_limits(&mut self, raw_line: &str) -> Result<(), StcError> {
+        self.bytes += raw_line.len() + if self.ln > 1 { self.opts.line_sep().len() } else { 0 };
+        if let Some(max) = self.opts.max_bytes
+            && self.bytes > max
+        {
+            return Err(err(ErrorKind::LimitExceeded, format!("Input exceeds max_bytes={max}."), Some(self.ln)));
+        }
+        // An empty line just past the limit may be the one after a final `\n`;
+        // it only counts once something follows it.
+        if let Some(max) = self.opts.max_lines {
+            let lines = self.other_lines + self.ln;
+            let over = if lines == max + 1 && raw_line.is_empty() { false } else { lines > max };
+            if over {
+                return Err(err(ErrorKind::LimitExceeded, format!("Input exceeds max_lines={max}."), Some(self.ln)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Replace each auto-index `$` with the next free index of its list, in
+    /// source order. A list must use either `$` or explicit `$N` indices.
+    fn resolve_auto_indices<S: AsRef<str>>(&mut self, path: &[S], ln: usize) -> Result<Vec<String>, StcError> {
+        let mut resolved: Vec<String> = Vec::with_capacity(path.len());
+        for piece in path {
+            let piece = piece.as_ref();
+            if !piece.starts_with('$') {
+                resolved.push(piece.to_string());
+                continue;
+            }
+            let prefix = resolved.join(".");
+            let auto = piece == AUTO_INDEX;
+            let mixed = if auto { self.explicit_index.contains(&prefix) } else { self.auto_index.contains_key(&prefix) };
+            if mixed {
+                let here = if prefix.is_empty() { "<root>" } else { &prefix };
+                return Err(err(
+                    ErrorKind::InvalidIndex,
+                    format!("List `{here}` mixes auto-assigned `$` and explicit `$N` indices; use one style per list."),
+                    Some(ln),
+                ));
+            }
+            if auto {
+                let next = self.auto_index.entry(prefix).or_insert(0);
+                resolved.push(format!("${next}"));
+                *next += 1;
+            } else {
+                self.explicit_index.insert(prefix);
+                resolved.push(piece.to_string());
+            }
+        }
+        Ok(resolved)
+    }
+
+    /// `fill_checked`, except that when warnings are being collected a line
+    /// whose key path conflicts with an earlier one (one is a prefix of the
+    /// other) is skipped and reported as a warning. Committed values are also
+    /// queued for `parse_stc_visit`.
+    fn fill(&mut self, path: &[String], value: Node, ln: usize) -> Result<(), StcError> {
+        let committed = self.commits.is_some().then(|| value.clone());
+        match (self.fill_checked(path, value, ln), &mut self.warnings) {
+            (Err(e @ StcError::Parse { kind: ErrorKind::KeyConflict, .. }), Some(warnings)) => {
+                warnings.push(Warning { path: join_key(path), message: e.to_string() });
+                Ok(())
+            }
+            (Ok(()), _) => {
+                if self.top_key.as_ref() != path.first() {
+                    self.top_key = path.first().cloned();
+                }
+                if let (Some(commits), Some(node)) = (&mut self.commits, committed) {
+                    commits.push((path.to_vec(), finalize_node(node, &join_key(path), None, None)?));
+                }
+                Ok(())
+            }
+            (res, _) => res,
+        }
+    }
+
+    /// `fill_in_value`, naming both lines when a list item is set twice.
+    fn fill_checked(&mut self, path: &[String], value: Node, ln: usize) -> Result<(), StcError> {
+        let track = self.opts.duplicate_keys == DuplicateKeys::Error && path.last().is_some_and(|p| p.starts_with('$'));
+        if !track {
+            return fill_in_value(&mut self.root, path, value, &self.opts, ln, self.value_span.take());
+        }
+        let key = path.join(".");
+        if let Some(&first) = self.item_lines.get(&key) {
+            let list = join_key(&path[..path.len() - 1]);
+            let here = if list.is_empty() { "<root>".to_string() } else { list };
+            let index = &path[path.len() - 1];
+            return Err(err(
+                ErrorKind::DuplicateValue,
+                format!("List `{here}` sets index {index} twice, on line {first} and line {ln}."),
+                Some(ln),
+            ));
+        }
+        fill_in_value(&mut self.root, path, value, &self.opts, ln, self.value_span.take())?;
+        self.item_lines.insert(key, ln);
+        Ok(())
+    }
+
+    fn check_index_order(&mut self, path: &[String], ln: usize) -> Result<(), StcError> {
+        for (i, piece) in path.iter().enumerate() {
+            // negative indices are positioned at the end, not by source order
+            let Some(Ok(idx)) = piece.strip_prefix('$').map(str::parse::<usize>) else { continue };
+            let prefix = path[..i].join(".");
+            let here = if prefix.is_empty() { "<root>" } else { &prefix };
+            let ok = match self.last_index.get(&prefix) {
+                None => idx == 0,
+                Some(&last) => idx == last || idx == last + 1,
+            };
+            if !ok {
+                let after = match self.last_index.get(&prefix) {
+                    None => "as its first index".to_string(),
+                    Some(last) => format!("after ${last}"),
+                };
+                return Err(err(
+                    ErrorKind::InvalidIndex,
+                    format!(
+                        "List `{here}` has index {piece} {after}; indices must appear \
+                         in ascending order starting at $0."
+                    ),
+                    Some(ln),
+                ));
+            }
+            self.last_index.insert(prefix, idx);
+        }
+        Ok(())
+    }
+
+    /// Finish the document and build the final `Value`.
+    pub fn finish(self) -> Result<Value, StcError> {
+        self.finish_spanned(None)
+    }
+
+    /// `finish`, collecting the recorded value spans into `spans`.
+    fn finish_spanned(self, spans: Option<&mut SpanSink>) -> Result<Value, StcError> {
+        self.check_closed()?;
+        let value = match self.root_empty_at {
+            Some((_, "[]")) => Value::Array(Vec::new()),
+            _ => finalize_node(self.root, "", self.opts.max_list_len, spans)?,
+        };
+        query::check_required(&value, &self.opts.required)?;
+        Ok(value)
+    }
+
+    /// Like `finish` for a document whose top level is a list, but yields the
+    /// items one at a time, each finalized only when it is reached.
+    fn finish_items(self) -> Result<impl Iterator<Item = Result<Value, StcError>>, StcError> {
+        self.check_closed()?;
+        let not_a_list = || StcError::InvalidArgument("The top-level value must be a list.".to_string());
+        let (mut d, slots) = match (self.root_empty_at, self.root) {
+            (Some((_, "[]")), _) => (HashMap::new(), Vec::new()),
+            (_, Node::Map(d, lines)) => {
+                let mut keys: Vec<String> = d.keys().cloned().collect();
+                keys.sort();
+                if !keys.first().is_some_and(|k| k.starts_with('$')) {
+                    return Err(not_a_list());
+                }
+                let slots = list_slots(&keys, &lines, "<root>", self.opts.max_list_len)?;
+                (d, slots)
+            }
+            _ => return Err(not_a_list()),
+        };
+        let max_list_len = self.opts.max_list_len;
+        Ok(slots.into_iter().map(move |(_, k)| {
+            let child = d.remove(&k).expect("list_slots returns the map's own keys");
+            finalize_node(child, &k, max_list_len, None)
+        }))
+    }
+
+    fn check_closed(&self) -> Result<(), StcError> {
+        if self.in_string {
+            // counts lines like `str::lines`, which ignores a final empty line
+            let lines = self.ln - usize::from(self.last_line_empty);
+            return Err(err(
+                ErrorKind::UnclosedString,
+                format!("Unclosed string block: the input ends at line {lines} without its closing {} fence.", self.string_fence),
+                Some(self.string_start_ln),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Consume a `---` document separator: finish the current document and
+    /// start a new one. Line numbers keep counting across documents.
+    pub fn end_document(&mut self) -> Result<Value, StcError> {
+        self.ln += 1;
+        self.check_limits(DOCUMENT_SEPARATOR)?;
+        if self.in_string {
+            return Err(err(
+                ErrorKind::UnclosedString,
+                format!(
+                    "Document separator `---` inside the string block starting at line {}.",
+                    self.string_start_ln
+                ),
+                Some(self.ln),
+            ));
+        }
+        let (ln, bytes) = (self.ln, self.bytes);
+        let done = std::mem::replace(self, StcParser::with_options(self.opts.clone()));
+        self.ln = ln;
+        self.bytes = bytes;
+        self.keep_lexemes = done.keep_lexemes;
+        done.finish()
+    }
+}
+
+/// Starts a comment line: outside string blocks, a line whose first
+/// non-whitespace character is `#` is ignored.
+pub const COMMENT: char = '#';
+
+/// Line separating documents in a multi-document stream.
+pub const DOCUMENT_SEPARATOR: &str = "---";
+
+/// Parse STC from &str into serde_json::Value
+pub fn parse_stc(input: &str) -> Result<Value, StcError> {
+    parse_stc_with(input, &ParseOptions::default())
+}
+
+/// Like `parse_stc`, with non-default parsing options.
+pub fn parse_stc_with(input: &str, opts: &ParseOptions) -> Result<Value, StcError> {
+    parse_in(StcParser::with_options(opts.clone()), input, opts)
+}
+
+/// `parse_stc_with`, keeping lexeme markers for the Python bindings and JSON output.
+pub(crate) fn parse_stc_raw(input: &str, opts: &ParseOptions) -> Result<Value, StcError> {
+    parse_in(StcParser::keeping_lexemes(opts.clone()), input, opts)
+}
+
+fn parse_in(mut parser: StcParser, input: &str, opts: &ParseOptions) -> Result<Value, StcError> {
+    for raw_line in opts.lines(input) {
+        parser.feed_line(raw_line)?;
+    }
+    parser.finish()
+}
+
+/// Like `parse_stc_with`, but also returns warnings an editor can surface
+/// without failing the parse: a line whose key path conflicts with an earlier
+/// one, as in `a: 1` followed by `a.b: 2`, is skipped and reported instead of
+/// raising, and the document is linted as by `lint`.
+///
+/// ```
+/// let (v, warnings) = stc_rust::parse_stc_with_warnings("a: 1\na.b: 2", &stc_rust::ParseOptions::new())?;
+/// assert_eq!(v, serde_json::json!({"a": 1}));
+/// assert_eq!(warnings[0].path, "a.b");
+/// # Ok::<(), stc_rust::StcError>(())
+/// ```
+pub fn parse_stc_with_warnings(input: &str, opts: &ParseOptions) -> Result<(Value, Vec<Warning>), StcError> {
+    warnings_in(StcParser::with_options(opts.clone()), input, opts)
+}
+
+/// `parse_stc_with_warnings`, keeping lexeme markers for the Python bindings.
+#[cfg(feature = "python")]
+pub(crate) fn parse_stc_with_warnings_raw(input: &str, opts: &ParseOptions) -> Result<(Value, Vec<Warning>), StcError> {
+    warnings_in(StcParser::keeping_lexemes(opts.clone()), input, opts)
+}
+
+fn warnings_in(mut parser: StcParser, input: &str, opts: &ParseOptions) -> Result<(Value, Vec<Warning>), StcError> {
+    parser.warnings = Some(Vec::new());
+    for raw_line in opts.lines(input) {
+        parser.feed_line(raw_line)?;
+    }
+    let mut warnings = parser.warnings.take().unwrap_or_default();
+    let value = parser.finish()?;
+    lint::lint_value(&value, "", &mut warnings);
+    Ok((value, warnings))
+}
+
+/// Like `parse_stc_with`, calling `visit` with the key path and value of each
+/// assignment as it is committed to the document.
+///
+/// Calls come in source order, one per assignment line (or string block),
+/// so a key may be seen before its siblings and list items before the list
+/// is checked for gaps. Paths are STC key segments: `$N` for list items with
+/// `$` and `$-N` resolved as far as the parser knows them, and quotes around
+/// keys that are not identifiers. An `!include` is one call with the whole
+/// included document, and a repeat that `duplicate_keys` drops is still seen.
+///
+/// ```
+/// let mut seen = Vec::new();
+/// let v = stc_rust::parse_stc_visit("a.b: 1\nc.$: `true`", &Default::default(), |path, value| {
+///     seen.push((path.join("."), value.clone()));
+/// })?;
+/// assert_eq!(seen, [("a.b".to_string(), serde_json::json!(1)), ("c.$0".to_string(), serde_json::json!(true))]);
+/// assert_eq!(v, serde_json::json!({"a": {"b": 1}, "c": [true]}));
+/// # Ok::<(), stc_rust::StcError>(())
+/// ```
+pub fn parse_stc_visit(
+    input: &str,
+    opts: &ParseOptions,
+    mut visit: impl FnMut(&[String], &Value),
+) -> Result<Value, StcError> {
+    let mut parser = StcParser::with_options(opts.clone());
+    parser.commits = Some(Vec::new());
+    for raw_line in opts.lines(input) {
+        parser.feed_line(raw_line)?;
+        for (path, value) in parser.commits.iter_mut().flat_map(|c| c.drain(..)) {
+            visit(&path, &value);
+        }
+    }
+    parser.finish()
+}
+
+/// Like `parse_stc_with`, also returning where each value is written, keyed
+/// by its JSON pointer in the result (`/servers/0/host`), so an editor can
+/// map a place in the output back to the source. Dicts and lists built from
+/// several lines have no span of their own; an inline list, an `!include`
+/// and a string block each have one span for the whole value.
+///
+/// ```
+/// let (v, spans) = stc_rust::parse_with_spans("servers.$0.host: ```\nexample.com\n```\nport: 80", &Default::default())?;
+/// assert_eq!(v["servers"][0]["host"], "example.com");
+/// let host = spans["/servers/0/host"];
+/// assert_eq!((host.line, host.col, host.end_line, host.end_col), (1, 18, 3, 4));
+/// assert_eq!((spans["/port"].line, spans["/port"].col), (4, 7));
+/// # Ok::<(), stc_rust::StcError>(())
+/// ```
+pub fn parse_with_spans(input: &str, opts: &ParseOptions) -> Result<(Value, BTreeMap<String, Span>), StcError> {
+    spans_in(StcParser::with_options(opts.clone()), input, opts)
+}
+
+/// `parse_with_spans`, keeping lexeme markers for the Python bindings.
+#[cfg(feature = "python")]
+pub(crate) fn parse_with_spans_raw(input: &str, opts: &ParseOptions) -> Result<(Value, BTreeMap<String, Span>), StcError> {
+    spans_in(StcParser::keeping_lexemes(opts.clone()), input, opts)
+}
+
+fn spans_in(mut parser: StcParser, input: &str, opts: &ParseOptions) -> Result<(Value, BTreeMap<String, Span>), StcError> {
+    parser.record_spans = true;
+    for raw_line in opts.lines(input) {
+        parser.feed_line(raw_line)?;
+    }
+    let mut sink = SpanSink::default();
+    let value = parser.finish_spanned(Some(&mut sink))?;
+    Ok((value, sink.spans))
+}
+
+/// Parse STC and list the typed empty collections in it, `ports: []int` or
+/// `labels: {}str`, as `(key path, element type)` pairs in document order.
+/// The parsed value itself only holds the plain empty list or dict.
+///
+/// ```
+/// let hints = stc_rust::empty_type_hints("ports: []int\nitems.$0.tags: {}str\nnone: []", &Default::default())?;
+/// assert_eq!(hints, [("ports".to_string(), "int"), ("items.$0.tags".to_string(), "str")]);
+/// # Ok::<(), stc_rust::StcError>(())
+/// ```
+pub fn empty_type_hints(input: &str, opts: &ParseOptions) -> Result<Vec<(String, &'static str)>, StcError> {
+    let mut parser = StcParser::with_options(opts.clone());
+    for raw_line in opts.lines(input) {
+        parser.feed_line(raw_line)?;
+    }
+    let written = std::mem::take(&mut parser.empty_hints);
+    let keep_first = parser.opts.duplicate_keys == DuplicateKeys::FirstWins;
+    let value = parser.finish()?;
+    let mut hints: Vec<(String, &'static str)> = Vec::new();
+    for (segments, ty) in written {
+        // The key may have been overwritten by a later line.
+        let Some((path, found)) = query::follow(&value, &segments) else { continue };
+        if !(found.as_array().is_some_and(Vec::is_empty) || found.as_object().is_some_and(Map::is_empty)) {
+            continue;
+        }
+        match hints.iter_mut().find(|(p, _)| *p == path) {
+            Some(_) if keep_first => {}
+            Some(seen) => seen.1 = ty.name(),
+            None => hints.push((path, ty.name())),
+        }
+    }
+    Ok(hints)
+}
+
+/// Parse a stream of STC documents separated by `---` lines.
+pub fn parse_stc_all(input: &str, opts: &ParseOptions) -> Result<Vec<Value>, StcError> {
+    all_in(StcParser::with_options(opts.clone()), input, opts)
+}
+
+/// `parse_stc_all`, keeping lexeme markers for the Python bindings.
+#[cfg(feature = "python")]
+pub(crate) fn parse_stc_all_raw(input: &str, opts: &ParseOptions) -> Result<Vec<Value>, StcError> {
+    all_in(StcParser::keeping_lexemes(opts.clone()), input, opts)
+}
+
+fn all_in(mut parser: StcParser, input: &str, opts: &ParseOptions) -> Result<Vec<Value>, StcError> {
+    let mut docs = Vec::new();
+    for raw_line in opts.lines(input) {
+        if raw_line == DOCUMENT_SEPARATOR {
+            docs.push(parser.end_document()?);
+        } else {
+            parser.feed_line(raw_line)?;
+        }
+    }
+    docs.push(parser.finish()?);
+    Ok(docs)
+}
+
+/// Parse one value as it would appear after `key:`, such as `` `true` ``,
+/// `42` or `"text"`. String blocks are rejected, since their content would
+/// be on the following lines.
+///
+/// ```
+/// assert_eq!(stc_rust::parse_scalar("2.5")?, serde_json::json!(2.5));
+/// assert!(stc_rust::parse_scalar("```").is_err());
+/// # Ok::<(), stc_rust::StcError>(())
+/// ```
+pub fn parse_scalar(raw: &str) -> Result<Value, StcError> {
+    match parse_value(raw.trim(), None, &ParseOptions::default())? {
+        ParsedValue::Immediate(n) => finalize_node(n, "", None, None),
+        ParsedValue::StringStart { .. } => Err(err(
+            ErrorKind::InvalidValue,
+            format!("Invalid value: {}. A string block needs content lines, so it is not a single value.", raw.trim()),
             None,
-        ));
+        )),
+    }
+}
+
+/// Parse STC and return it as JSON text, indented by `indent` spaces per
+/// level. `None` or `Some(0)` gives compact output.
+pub fn to_json(input: &str, indent: Option<usize>) -> Result<String, StcError> {
+    to_json_with(input, indent, &ParseOptions::default())
+}
+
+/// Like `to_json`, with non-default parsing options. Numbers kept by
+/// `keep_float_text` or `preserve_number_lexemes` are written with their
+/// source text, so `x: 1.50` gives `{"x":1.50}`.
+///
+/// ```
+/// let opts = stc_rust::ParseOptions::new().keep_float_text(true);
+/// assert_eq!(stc_rust::to_json_with("x: 1.50", None, &opts)?, r#"{"x":1.50}"#);
+/// # Ok::<(), stc_rust::StcError>(())
+/// ```
+pub fn to_json_with(input: &str, indent: Option<usize>, opts: &ParseOptions) -> Result<String, StcError> {
+    let value = parse_stc_raw(input, opts)?;
+    let value = lexeme::JsonOut(&value, opts);
+    let Some(width) = indent.filter(|&w| w > 0) else {
+        return serde_json::to_string(&value).map_err(StcError::json);
+    };
+    let pad = " ".repeat(width);
+    let mut out = Vec::new();
+    let mut ser = serde_json::Serializer::with_formatter(&mut out, serde_json::ser::PrettyFormatter::with_indent(pad.as_bytes()));
+    serde::Serialize::serialize(&value, &mut ser).map_err(StcError::json)?;
+    Ok(String::from_utf8(out).expect("serde_json writes UTF-8"))
+}
+
+/// Parse STC and return a canonical JSON text for hashing and change
+/// detection: compact, with every object's keys sorted, floats in their
+/// shortest round-trip form (`-0.0` as `0.0`) and base64 data as strings.
+/// Documents that differ only in line order or whitespace give the same text.
+///
+/// ```
+/// let a = stc_rust::canonical_json("b: 1.50\na.y: `true`\na.x: 2")?;
+/// assert_eq!(a, r#"{"a":{"x":2,"y":true},"b":1.5}"#);
+/// assert_eq!(a, stc_rust::canonical_json("a.x: 2\n  b: 1.5\na.y: `true`")?);
+/// # Ok::<(), stc_rust::StcError>(())
+/// ```
+pub fn canonical_json(input: &str) -> Result<String, StcError> {
+    fn canonicalize(v: Value) -> Value {
+        match v {
+            Value::Object(obj) => {
+                let mut entries: Vec<(String, Value)> = obj.into_iter().collect();
+                entries.sort_by(|a, b| a.0.cmp(&b.0));
+                Value::Object(entries.into_iter().map(|(k, v)| (k, canonicalize(v))).collect())
+            }
+            Value::Array(arr) => Value::Array(arr.into_iter().map(canonicalize).collect()),
+            Value::Number(n) if n.as_f64() == Some(0.0) && n.is_f64() => Value::from(0.0),
+            other => other,
+        }
     }
+    let mut value = parse_stc(input)?;
+    base64::markers_to_strings(&mut value);
+    serde_json::to_string(&canonicalize(value)).map_err(StcError::json)
+}
+
+/// Parse STC whose top level is a list into JSON Lines: one compact JSON
+/// value per item, each followed by `\n`. Items are finalized and written one
+/// at a time, so the whole document's `Value` is never built.
+///
+/// ```
+/// let mut out = Vec::new();
+/// stc_rust::write_jsonl("$0.id: 1\n$1.id: 2", &mut out)?;
+/// assert_eq!(out, b"{\"id\":1}\n{\"id\":2}\n");
+/// # Ok::<(), stc_rust::StcError>(())
+/// ```
+pub fn write_jsonl<W: std::io::Write>(input: &str, out: &mut W) -> Result<(), StcError> {
+    write_jsonl_with(input, out, &ParseOptions::default())
+}
 
-    finalize_node(root, "")
+/// Like `write_jsonl`, with non-default parsing options. As in `to_json_with`,
+/// numbers kept by `keep_float_text` or `preserve_number_lexemes` are written
+/// with their source text.
+pub fn write_jsonl_with<W: std::io::Write>(input: &str, out: &mut W, opts: &ParseOptions) -> Result<(), StcError> {
+    for_each_jsonl_line(input, opts, |line: &str| out.write_all(line.as_bytes()).map_err(StcError::from))
 }
 
-#[pyfunction]
-fn loads(py: Python<'_>, s: &str) -> PyResult<PyObject> {
-    let val = parse_stc(s)?;
-    value_to_pyobj(py, &val)
+/// `write_jsonl` into a string.
+pub fn to_jsonl(input: &str) -> Result<String, StcError> {
+    to_jsonl_with(input, &ParseOptions::default())
 }
 
-#[pymodule]
-fn stc_rust(_py: Python<'_>, m: &Bound<PyModule>) -> PyResult<()> {
-    m.add_function(wrap_pyfunction!(loads, m)?)?;
+/// `write_jsonl_with` into a string.
+pub fn to_jsonl_with(input: &str, opts: &ParseOptions) -> Result<String, StcError> {
+    let mut out = String::new();
+    for_each_jsonl_line(input, opts, |line: &str| {
+        out.push_str(line);
+        Ok::<_, StcError>(())
+    })?;
+    Ok(out)
+}
+
+/// Hand each line of `write_jsonl_with`'s output to `emit`, newline included.
+pub(crate) fn for_each_jsonl_line<E: From<StcError>>(
+    input: &str,
+    opts: &ParseOptions,
+    mut emit: impl FnMut(&str) -> Result<(), E>,
+) -> Result<(), E> {
+    let mut parser = StcParser::keeping_lexemes(opts.clone());
+    for raw_line in opts.lines(input) {
+        parser.feed_line(raw_line)?;
+    }
+    for item in parser.finish_items()? {
+        let mut line = serde_json::to_string(&lexeme::JsonOut(&item?, opts)).map_err(StcError::json)?;
+        line.push('\n');
+        emit(&line)?;
+    }
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Parse STC and deserialize the result into `T`.
+///
+/// This is `parse_stc` followed by `serde_json::from_value`, so any type
+/// implementing `Deserialize` can be read straight from an STC document.
+/// Base64 blocks deserialize as their base64 text, as in `to_json`.
+pub fn from_str<T: DeserializeOwned>(input: &str) -> Result<T, StcError> {
+    let mut value = parse_stc(input)?;
+    base64::markers_to_strings(&mut value);
+    serde_json::from_value(value).map_err(StcError::Deserialize)
+}
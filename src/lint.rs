@@ -0,0 +1,78 @@
+//! Post-parse lints that flag suspicious but valid documents.
+
+use std::collections::HashMap;
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use serde_json::Value;
+
+use crate::{parse_stc, StcError};
+
+/// A non-fatal finding about a document that parsed successfully; see
+/// `lint` and `parse_stc_with_warnings`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    /// Dotted path of the container holding the offending keys (`""` for the
+    /// root), or of the skipped line's key for a key conflict.
+    pub path: String,
+    pub message: String,
+}
+
+/// Parse `input` and report keys that are likely duplicates of one another.
+///
+/// Two sibling keys are near-duplicates when they are equal after trimming
+/// whitespace and ignoring case, e.g. `Server` and `server`.
+pub fn lint(input: &str) -> Result<Vec<Warning>, StcError> {
+    let value = parse_stc(input)?;
+    let mut warnings = Vec::new();
+    lint_value(&value, "", &mut warnings);
+    Ok(warnings)
+}
+
+pub(crate) fn lint_value(v: &Value, prefix: &str, out: &mut Vec<Warning>) {
+    let join = |k: &str| if prefix.is_empty() { k.to_string() } else { format!("{prefix}.{k}") };
+    match v {
+        Value::Object(obj) => {
+            let mut seen: HashMap<String, &str> = HashMap::new();
+            for k in obj.keys() {
+                let norm = k.trim().to_lowercase();
+                if let Some(first) = seen.get(&norm) {
+                    out.push(Warning {
+                        path: prefix.to_string(),
+                        message: format!(
+                            "Keys `{}` and `{}` differ only by case or whitespace; likely a duplicate.",
+                            join(first),
+                            join(k)
+                        ),
+                    });
+                } else {
+                    seen.insert(norm, k);
+                }
+            }
+            for (k, child) in obj {
+                lint_value(child, &join(k), out);
+            }
+        }
+        Value::Array(arr) => {
+            for (i, child) in arr.iter().enumerate() {
+                lint_value(child, &join(&format!("${i}")), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(feature = "python")]
+#[pyfunction(name = "lint")]
+pub(crate) fn py_lint(s: &str) -> PyResult<Vec<String>> {
+    Ok(lint(s)?.into_iter().map(|w| w.message).collect())
+}
+
+/// Check that `s` is valid STC, raising on the first error. The parsed value is
+/// dropped on the Rust side, so no Python objects are built.
+#[cfg(feature = "python")]
+#[pyfunction(name = "validate")]
+pub(crate) fn py_validate(py: Python<'_>, s: &str) -> PyResult<()> {
+    py.allow_threads(|| parse_stc(s))?;
+    Ok(())
+}
@@ -0,0 +1,81 @@
+//! Deep merging of parsed documents, for layering an overlay config on a base.
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3::types::{PyDict, PyList};
+use serde_json::Value;
+
+/// Options for `merge_values_with`.
+#[derive(Debug, Clone, Default)]
+pub struct MergeOptions {
+    /// Append overlay arrays to base arrays instead of replacing them.
+    pub concat_arrays: bool,
+}
+
+/// Deep-merge `overlay` into `base`: objects merge key by key, arrays are
+/// replaced wholesale and any other overlay value replaces the base value.
+pub fn merge_values(base: &mut Value, overlay: &Value) {
+    merge_values_with(base, overlay, &MergeOptions::default());
+}
+
+/// Like `merge_values`, with non-default options.
+pub fn merge_values_with(base: &mut Value, overlay: &Value, opts: &MergeOptions) {
+    match (base, overlay) {
+        (Value::Object(b), Value::Object(o)) => {
+            for (k, ov) in o {
+                match b.get_mut(k) {
+                    Some(bv) => merge_values_with(bv, ov, opts),
+                    None => {
+                        b.insert(k.clone(), ov.clone());
+                    }
+                }
+            }
+        }
+        (Value::Array(b), Value::Array(o)) if opts.concat_arrays => b.extend(o.iter().cloned()),
+        (b, o) => *b = o.clone(),
+    }
+}
+
+#[cfg(feature = "python")]
+fn merge_pyobj<'py>(
+    base: &Bound<'py, PyAny>,
+    overlay: &Bound<'py, PyAny>,
+    opts: &MergeOptions,
+) -> PyResult<Bound<'py, PyAny>> {
+    let py = base.py();
+    if let (Ok(b), Ok(o)) = (base.downcast::<PyDict>(), overlay.downcast::<PyDict>()) {
+        let out = PyDict::new(py);
+        for (k, v) in b.iter() {
+            out.set_item(k, v)?;
+        }
+        for (k, ov) in o.iter() {
+            match b.get_item(&k)? {
+                Some(bv) => out.set_item(&k, merge_pyobj(&bv, &ov, opts)?)?,
+                None => out.set_item(&k, ov)?,
+            }
+        }
+        return Ok(out.into_any());
+    }
+    if let (true, Ok(b), Ok(o)) = (opts.concat_arrays, base.downcast::<PyList>(), overlay.downcast::<PyList>()) {
+        let out = PyList::new(py, b.iter())?;
+        for item in o.iter() {
+            out.append(item)?;
+        }
+        return Ok(out.into_any());
+    }
+    Ok(overlay.clone())
+}
+
+/// Python `merge(base, overlay)`: returns a new merged dict and leaves both
+/// inputs untouched. Nested containers that are not merged are shared.
+#[cfg(feature = "python")]
+#[pyfunction]
+#[pyo3(signature = (base, overlay, *, concat_arrays=false))]
+pub(crate) fn merge<'py>(
+    base: &Bound<'py, PyAny>,
+    overlay: &Bound<'py, PyAny>,
+    concat_arrays: bool,
+) -> PyResult<Bound<'py, PyAny>> {
+    merge_pyobj(base, overlay, &MergeOptions { concat_arrays })
+}
@@ -0,0 +1,612 @@
+//! Python bindings: the `stc_rust` extension module, conversion of parsed
+//! values into Python objects, and the mapping of `StcError` onto exceptions.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use pyo3::prelude::*;
+use pyo3::{create_exception, exceptions::{PyException, PyKeyError, PyTypeError, PyValueError}, PyErr};
+use pyo3::types::{PyBool, PyBytes, PyDict, PyFloat, PyList, PyInt, PyString, PyTuple};
+use serde_json::Value;
+
+use crate::{
+    base64, cache, dumps, finalize_node, gzip, include, lexeme, lint, merge, parse_stc_all_raw, parse_stc_raw, parse_stc_with_warnings_raw, query,
+    reader, ParseOptions, StcError,
+};
+
+create_exception!(stc_rust, STCParseError, PyException);
+
+impl From<StcError> for PyErr {
+    fn from(e: StcError) -> Self {
+        match e {
+            StcError::Serialize(_) | StcError::InvalidArgument(_) => PyValueError::new_err(e.to_string()),
+            StcError::Io(e) => e.into(),
+            StcError::Parse { line, column, kind, .. } => Python::with_gil(|py| {
+                let pe = STCParseError::new_err(e.to_string());
+                // Setting attributes on a fresh exception instance cannot fail.
+                let v = pe.value(py);
+                let _ = v.setattr("lineno", line);
+                let _ = v.setattr("colno", column);
+                let _ = v.setattr("kind", kind.as_str());
+                pe
+            }),
+            _ => STCParseError::new_err(e.to_string()),
+        }
+    }
+}
+
+/// Controls how a parsed `Value` is turned into Python objects.
+#[derive(Debug, Default)]
+pub(crate) struct ToPyOptions {
+    /// Wrap every dict in a read-only `StcView`.
+    readonly: bool,
+    /// Build a structure whose `==` is stable across runs: dicts with sorted
+    /// keys, tuples instead of lists, and floats rounded to `float_precision`.
+    normalize: bool,
+    /// Decimal places kept for floats when `normalize` is set, at most 17 (`None`
+    /// keeps them all).
+    float_precision: Option<usize>,
+    /// Build floats as `decimal.Decimal` from their source text; needs
+    /// `ParseOptions::keep_float_text`.
+    use_decimal: bool,
+    /// Mapping class to build objects with instead of `dict`: called with no
+    /// arguments, then filled through `__setitem__`.
+    dict_class: Option<PyObject>,
+    /// With `intern_keys`, one `str` per distinct dict key, shared by every
+    /// dict that uses it.
+    key_pool: Option<RefCell<HashMap<String, Py<PyString>>>>,
+    /// The options the document was parsed with, so preserved number
+    /// lexemes become the int or float the parser typed them as.
+    parse: ParseOptions,
+}
+
+/// The Python key for `k`, from the key pool when there is one.
+fn py_key<'py>(py: Python<'py>, k: &str, opts: &ToPyOptions) -> Bound<'py, PyString> {
+    let Some(pool) = &opts.key_pool else {
+        return PyString::new(py, k);
+    };
+    let mut pool = pool.borrow_mut();
+    if let Some(s) = pool.get(k) {
+        return s.bind(py).clone();
+    }
+    let s = PyString::new(py, k);
+    pool.insert(k.to_string(), s.clone().unbind());
+    s
+}
+
+pub(crate) fn value_to_pyobj(py: Python<'_>, v: &Value, opts: &ToPyOptions) -> PyResult<PyObject> {
+    Ok(match v {
+        Value::Null => py.None(), // Py<PyAny> == PyObject
+
+        Value::Bool(b) => {
+            // Bound<PyAny>
+            let any = <pyo3::Bound<'_, PyBool> as Clone>::clone(&PyBool::new(py, *b)).into_any();
+            any.unbind()
+        }
+
+        Value::Number(num) => {
+            if let Some(i) = num.as_i64() {
+                PyInt::new(py, i).into_any().unbind()
+            } else if let Some(u) = num.as_u64() {
+                PyInt::new(py, u).into_any().unbind()
+            } else if let Some(f) = num.as_f64() {
+                // round through the decimal text, which cannot overflow like `f * 10^p`
+                let f = match (opts.normalize, opts.float_precision) {
+                    (true, Some(p)) => format!("{f:.p$}").parse().unwrap_or(f),
+                    _ => f,
+                };
+                PyFloat::new(py, f).into_any().unbind()
+            } else {
+                return Err(STCParseError::new_err("Invalid JSON number"));
+            }
+        }
+
+        Value::String(s) => PyString::new(py, s).into_any().unbind(),
+
+        Value::Array(arr) if opts.normalize => {
+            let items = arr.iter().map(|item| value_to_pyobj(py, item, opts)).collect::<PyResult<Vec<_>>>()?;
+            PyTuple::new(py, items)?.into_any().unbind()
+        }
+
+        Value::Array(arr) => {
+            let list = PyList::empty(py);
+            for item in arr {
+                // value_to_pyobj -> PyObject, bind to this GIL to append
+                list.append(value_to_pyobj(py, item, opts)?.bind(py))?;
+            }
+            list.into_any().unbind()
+        }
+
+        Value::Object(_) if base64::bytes_of(v).is_some() => {
+            let data = base64::bytes_of(v).and_then(base64::decode).unwrap_or_default();
+            PyBytes::new(py, &data).into_any().unbind()
+        }
+
+        Value::Object(_) if lexeme::lexeme_of(v).is_some() => {
+            let raw = lexeme::lexeme_of(v).unwrap_or_default();
+            if opts.normalize && !opts.use_decimal {
+                let n = lexeme::lexeme_node(raw, &opts.parse).and_then(|n| finalize_node(n, "", None, None).ok()).unwrap_or_default();
+                value_to_pyobj(py, &n, opts)?
+            } else {
+                lexeme::to_py(py, raw, opts.use_decimal, &opts.parse)?
+            }
+        }
+
+        Value::Object(obj) => {
+            if let Some(cls) = &opts.dict_class {
+                let mapping = cls.bind(py).call0()?;
+                for (k, val) in entries(obj, opts) {
+                    mapping.set_item(py_key(py, k, opts), value_to_pyobj(py, val, opts)?.bind(py))?;
+                }
+                return Ok(mapping.unbind());
+            }
+            let dict = PyDict::new(py);
+            set_entries(py, &dict, obj, opts)?;
+            if opts.readonly {
+                Py::new(py, StcView { data: dict.unbind() })?.into_any()
+            } else {
+                dict.into_any().unbind()
+            }
+        }
+    })
+}
+
+/// The entries of `obj` in the order they are converted: sorted when
+/// normalizing, so `==` is stable.
+fn entries<'v>(obj: &'v serde_json::Map<String, Value>, opts: &ToPyOptions) -> Vec<(&'v String, &'v Value)> {
+    let mut entries: Vec<(&String, &Value)> = obj.iter().collect();
+    if opts.normalize {
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+    }
+    entries
+}
+
+/// Convert the entries of `obj` into `dict`.
+fn set_entries(py: Python<'_>, dict: &Bound<'_, PyDict>, obj: &serde_json::Map<String, Value>, opts: &ToPyOptions) -> PyResult<()> {
+    for (k, val) in entries(obj, opts) {
+        dict.set_item(py_key(py, k, opts), value_to_pyobj(py, val, opts)?.bind(py))?;
+    }
+    Ok(())
+}
+
+/// Read-only mapping returned by `loads(..., readonly=True)`.
+///
+/// Missing keys raise `KeyError` naming the closest existing key, so typos in
+/// config lookups are caught at read time.
+#[pyclass(name = "StcView", module = "stc.stc_rust", frozen, mapping)]
+struct StcView {
+    data: Py<PyDict>,
+}
+
+#[pymethods]
+impl StcView {
+    fn __getitem__(&self, py: Python<'_>, key: &Bound<'_, PyAny>) -> PyResult<PyObject> {
+        let data = self.data.bind(py);
+        match data.get_item(key)? {
+            Some(v) => Ok(v.unbind()),
+            None => Err(PyKeyError::new_err(self.missing_key_message(py, key)?)),
+        }
+    }
+
+    #[pyo3(signature = (key, default=None))]
+    fn get(&self, py: Python<'_>, key: &Bound<'_, PyAny>, default: Option<PyObject>) -> PyResult<PyObject> {
+        match self.data.bind(py).get_item(key)? {
+            Some(v) => Ok(v.unbind()),
+            None => Ok(default.unwrap_or_else(|| py.None())),
+        }
+    }
+
+    fn __contains__(&self, py: Python<'_>, key: &Bound<'_, PyAny>) -> PyResult<bool> {
+        self.data.bind(py).contains(key)
+    }
+
+    fn __len__(&self, py: Python<'_>) -> usize {
+        self.data.bind(py).len()
+    }
+
+    fn __iter__(&self, py: Python<'_>) -> PyResult<PyObject> {
+        Ok(self.data.bind(py).try_iter()?.into_any().unbind())
+    }
+
+    fn keys(&self, py: Python<'_>) -> PyObject {
+        self.data.bind(py).keys().into_any().unbind()
+    }
+
+    fn values(&self, py: Python<'_>) -> PyObject {
+        self.data.bind(py).values().into_any().unbind()
+    }
+
+    fn items(&self, py: Python<'_>) -> PyObject {
+        self.data.bind(py).items().into_any().unbind()
+    }
+
+    fn __eq__(&self, py: Python<'_>, other: &Bound<'_, PyAny>) -> PyResult<bool> {
+        match other.downcast::<StcView>() {
+            Ok(view) => self.data.bind(py).eq(view.get().data.bind(py)),
+            Err(_) => self.data.bind(py).eq(other),
+        }
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        Ok(format!("StcView({})", self.data.bind(py).repr()?))
+    }
+}
+
+impl StcView {
+    fn missing_key_message(&self, py: Python<'_>, key: &Bound<'_, PyAny>) -> PyResult<String> {
+        let Ok(wanted) = key.extract::<String>() else {
+            return Ok(format!("{}", key.repr()?));
+        };
+        let mut best: Option<(usize, String)> = None;
+        for k in self.data.bind(py).keys() {
+            let Ok(k) = k.extract::<String>() else { continue };
+            let d = edit_distance(&wanted, &k);
+            if best.as_ref().is_none_or(|(bd, _)| d < *bd) {
+                best = Some((d, k));
+            }
+        }
+        Ok(match best {
+            Some((d, k)) if d <= (wanted.chars().count() / 3).max(2) => {
+                format!("Unknown key `{wanted}`, did you mean `{k}`?")
+            }
+            _ => format!("Unknown key `{wanted}`."),
+        })
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+/// Extract a `loads` keyword argument, naming it in the error.
+fn kwarg<'py, T: FromPyObject<'py>>(name: &str, v: &Bound<'py, PyAny>) -> PyResult<T> {
+    v.extract().map_err(|e| PyTypeError::new_err(format!("loads() argument `{name}`: {e}")))
+}
+
+/// `loads` keyword arguments that change how it runs rather than what it parses.
+#[derive(Default)]
+struct LoadFlags {
+    cache: bool,
+    return_warnings: bool,
+}
+
+/// Map `loads` keyword arguments onto `ParseOptions`, `ToPyOptions` and
+/// `LoadFlags`. Options not given keep their defaults.
+fn load_options(py: Python<'_>, kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<(ParseOptions, ToPyOptions, LoadFlags)> {
+    let (mut opts, mut to_py, mut flags) = (ParseOptions::default(), ToPyOptions::default(), LoadFlags::default());
+    for (k, v) in kwargs.into_iter().flat_map(|d| d.iter()) {
+        let name: String = k.extract()?;
+        match name.as_str() {
+            "readonly" => to_py.readonly = kwarg(&name, &v)?,
+            "normalize" => to_py.normalize = kwarg(&name, &v)?,
+            "float_precision" => {
+                let p: i64 = kwarg(&name, &v)?;
+                if !(0..=17).contains(&p) {
+                    return Err(StcError::InvalidArgument(format!(
+                        "`float_precision` must be between 0 and 17, not {p}; a float has no more digits to round to."
+                    ))
+                    .into());
+                }
+                to_py.float_precision = Some(p as usize);
+            }
+            "use_decimal" => {
+                to_py.use_decimal = kwarg(&name, &v)?;
+                opts.keep_float_text = to_py.use_decimal;
+            }
+            "cache" => flags.cache = kwarg(&name, &v)?,
+            "return_warnings" => flags.return_warnings = kwarg(&name, &v)?,
+            "intern_keys" => to_py.key_pool = kwarg::<bool>(&name, &v)?.then(RefCell::default),
+            // `dict` itself takes the fast path
+            "dict_class" if v.is(py.get_type::<PyDict>()) => to_py.dict_class = None,
+            "dict_class" => to_py.dict_class = Some(v.unbind()),
+            "keep_trailing_newline" => opts.keep_trailing_newline = kwarg(&name, &v)?,
+            "dedent" => opts.dedent = kwarg(&name, &v)?,
+            "require_ordered_indices" => opts.require_ordered_indices = kwarg(&name, &v)?,
+            "duplicate_keys" => opts.duplicate_keys = kwarg::<String>(&name, &v)?.parse()?,
+            "validate_block_content" => opts.validate_block_content = kwarg(&name, &v)?,
+            "preserve_number_lexemes" => opts.preserve_number_lexemes = kwarg(&name, &v)?,
+            // the inverse of the Rust name: `coerce_numbers=False` is `strict_types`
+            "coerce_numbers" => opts.strict_types = !kwarg::<bool>(&name, &v)?,
+            "max_bytes" => opts.max_bytes = kwarg(&name, &v)?,
+            "max_lines" => opts.max_lines = kwarg(&name, &v)?,
+            "strip_trailing_whitespace_in_blocks" => opts.strip_trailing_whitespace_in_blocks = kwarg(&name, &v)?,
+            "max_list_len" => opts.max_list_len = kwarg(&name, &v)?,
+            "pretty_errors" => opts.pretty_errors = kwarg(&name, &v)?,
+            "scientific_ints" => opts.scientific_ints = kwarg(&name, &v)?,
+            "float_to_int_when_whole" => opts.float_to_int_when_whole = kwarg(&name, &v)?,
+            "allow_indented_fence" => opts.allow_indented_fence = kwarg(&name, &v)?,
+            "case_insensitive_literals" => opts.case_insensitive_literals = kwarg(&name, &v)?,
+            "empty_values" => opts.empty_values = kwarg::<String>(&name, &v)?.parse()?,
+            "required" => opts.required = kwarg(&name, &v)?,
+            "allow_includes" => opts.allow_includes = kwarg(&name, &v)?,
+            "open_empty_collections" => opts.open_empty_collections = kwarg(&name, &v)?,
+            "line_separator" => {
+                let sep: String = kwarg(&name, &v)?;
+                if sep.is_empty() {
+                    return Err(StcError::InvalidArgument("`line_separator` must not be empty.".to_string()).into());
+                }
+                opts.line_separator = Some(sep);
+            }
+            "separator" => {
+                let sep: String = kwarg(&name, &v)?;
+                if sep.is_empty() {
+                    return Err(StcError::InvalidArgument("`separator` must not be empty.".to_string()).into());
+                }
+                opts.separator = Some(sep);
+            }
+            _ => return Err(PyTypeError::new_err(format!("loads() got an unexpected keyword argument `{name}`"))),
+        }
+    }
+    if to_py.float_precision.is_some() && !to_py.normalize {
+        return Err(StcError::InvalidArgument("`float_precision` only applies with `normalize=True`.".to_string()).into());
+    }
+    if to_py.readonly && to_py.dict_class.is_some() {
+        return Err(StcError::InvalidArgument("`readonly` and `dict_class` cannot be combined.".to_string()).into());
+    }
+    to_py.parse = opts.clone();
+    Ok((opts, to_py, flags))
+}
+
+/// `load_options` for functions that return text rather than Python objects:
+/// only the parsing options apply, so the ones that shape Python objects are
+/// rejected rather than ignored.
+fn text_options(py: Python<'_>, kwargs: Option<&Bound<'_, PyDict>>, func: &str) -> PyResult<ParseOptions> {
+    const PY_ONLY: [&str; 7] = ["readonly", "normalize", "float_precision", "intern_keys", "dict_class", "cache", "return_warnings"];
+    for k in kwargs.into_iter().flat_map(|d| d.keys()) {
+        let name: String = k.extract()?;
+        if PY_ONLY.contains(&name.as_str()) {
+            return Err(StcError::InvalidArgument(format!("`{name}` only applies to `loads`, not to `{func}`.")).into());
+        }
+    }
+    Ok(load_options(py, kwargs)?.0)
+}
+
+/// Parse STC into Python objects. Keyword options map onto `ParseOptions`
+/// (parsing) and `ToPyOptions` (conversion); see `load_options`. With
+/// `return_warnings=True` the result is a `(value, warnings)` tuple, as from
+/// `parse_stc_with_warnings`, with one message per warning.
+#[pyfunction]
+#[pyo3(signature = (s, **kwargs))]
+fn loads(py: Python<'_>, s: &str, kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<PyObject> {
+    let (opts, to_py, flags) = load_options(py, kwargs)?;
+    if flags.return_warnings {
+        let (val, warnings) = parse_stc_with_warnings_raw(s, &opts)?;
+        let messages: Vec<String> = warnings.into_iter().map(|w| w.message).collect();
+        let pair = (value_to_pyobj(py, &val, &to_py)?, messages);
+        return Ok(pair.into_pyobject(py)?.into_any().unbind());
+    }
+    let val = if flags.cache { cache::parse_cached(s, &opts)? } else { parse_stc_raw(s, &opts)? };
+    value_to_pyobj(py, &val, &to_py)
+}
+
+/// `loads` without raising on bad input: `(value, None)` when `s` parses,
+/// `(None, message)` when it does not, the message being what
+/// `STCParseError` would show. Invalid keyword options still raise.
+#[pyfunction]
+#[pyo3(signature = (s, **kwargs))]
+fn try_loads(py: Python<'_>, s: &str, kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<(PyObject, Option<String>)> {
+    let (opts, to_py, flags) = load_options(py, kwargs)?;
+    if flags.return_warnings {
+        return Err(StcError::InvalidArgument("`return_warnings` is only supported by `loads`.".to_string()).into());
+    }
+    let parsed = if flags.cache { cache::parse_cached(s, &opts) } else { parse_stc_raw(s, &opts) };
+    match parsed {
+        Ok(val) => Ok((value_to_pyobj(py, &val, &to_py)?, None)),
+        Err(e) => Ok((py.None(), Some(e.to_string()))),
+    }
+}
+
+/// Parse STC into the existing dict `target`, which is cleared first, instead
+/// of building a new one; for callers reusing one dict in a loop. The
+/// document's top level must be a dict. `target` is left untouched when `s`
+/// does not parse.
+#[pyfunction]
+#[pyo3(signature = (s, target, **kwargs))]
+fn loads_into(py: Python<'_>, s: &str, target: &Bound<'_, PyDict>, kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<()> {
+    let (opts, to_py, flags) = load_options(py, kwargs)?;
+    for (set, name) in [(flags.return_warnings, "return_warnings"), (to_py.readonly, "readonly"), (to_py.dict_class.is_some(), "dict_class")] {
+        if set {
+            return Err(StcError::InvalidArgument(format!("`{name}` cannot be used with `loads_into`.")).into());
+        }
+    }
+    let val = if flags.cache { cache::parse_cached(s, &opts)? } else { parse_stc_raw(s, &opts)? };
+    let Value::Object(obj) = &val else {
+        return Err(StcError::InvalidArgument("`loads_into` needs a document whose top level is a dict, not a list.".to_string()).into());
+    };
+    target.clear();
+    set_entries(py, target, obj, &to_py)
+}
+
+/// Parse the STC file at `path`, resolving `!include` values relative to it
+/// when `allow_includes=True`. Takes the same keyword options as `loads`.
+/// With `read`, every file is read by calling `read(path)` for its text, so
+/// `stc.load` can decompress and decode them like any other file; an
+/// exception it raises propagates unchanged.
+#[pyfunction]
+#[pyo3(signature = (path, *, read=None, **kwargs))]
+fn load_file(
+    py: Python<'_>,
+    path: std::path::PathBuf,
+    read: Option<PyObject>,
+    kwargs: Option<&Bound<'_, PyDict>>,
+) -> PyResult<PyObject> {
+    let (opts, to_py, flags) = load_options(py, kwargs)?;
+    if flags.return_warnings {
+        return Err(StcError::InvalidArgument("`return_warnings` is only supported by `loads`.".to_string()).into());
+    }
+    let Some(read) = read else {
+        return value_to_pyobj(py, &include::parse_file_with(&path, &opts, None)?, &to_py);
+    };
+    // the reader's exception is kept aside and raised in place of the parse error
+    let raised: Arc<Mutex<Option<PyErr>>> = Arc::default();
+    let stash = Arc::clone(&raised);
+    let reader = move |p: &std::path::Path| {
+        Python::with_gil(|py| read.call1(py, (p,))?.extract::<String>(py)).map_err(|e| {
+            let msg = e.to_string();
+            *stash.lock().unwrap_or_else(PoisonError::into_inner) = Some(e);
+            StcError::Io(std::io::Error::other(msg))
+        })
+    };
+    match include::parse_file_with(&path, &opts, Some(Arc::new(reader))) {
+        Ok(val) => value_to_pyobj(py, &val, &to_py),
+        Err(e) => Err(raised.lock().unwrap_or_else(PoisonError::into_inner).take().unwrap_or_else(|| e.into())),
+    }
+}
+
+/// Parse a `---`-separated stream of STC documents into a list. Takes the
+/// keyword options of `loads`, applied to every document.
+#[pyfunction]
+#[pyo3(signature = (s, **kwargs))]
+fn loads_all(py: Python<'_>, s: &str, kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<PyObject> {
+    let (opts, to_py, flags) = load_options(py, kwargs)?;
+    for (set, name) in [(flags.return_warnings, "return_warnings"), (flags.cache, "cache")] {
+        if set {
+            return Err(StcError::InvalidArgument(format!("`{name}` cannot be used with `loads_all`.")).into());
+        }
+    }
+    let docs = parse_stc_all_raw(s, &opts)?;
+    let list = PyList::empty(py);
+    for doc in &docs {
+        list.append(value_to_pyobj(py, doc, &to_py)?)?;
+    }
+    Ok(list.into_any().unbind())
+}
+
+/// Parse STC and return the result as a JSON string, without building Python
+/// objects. Faster than `json.dumps(loads(s))` for callers that want text.
+/// Takes the parsing options of `loads`; see `text_options`.
+#[pyfunction]
+#[pyo3(signature = (s, **kwargs))]
+fn loads_json_str(py: Python<'_>, s: &str, kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<String> {
+    let opts = text_options(py, kwargs, "loads_json_str")?;
+    let val = parse_stc_raw(s, &opts)?;
+    Ok(serde_json::to_string(&lexeme::JsonOut(&val, &opts)).map_err(StcError::json)?)
+}
+
+/// Parse STC and return it as JSON text, indented by `indent` spaces per
+/// level (`0` or `None` for compact output). With `keep_float_text`, floats
+/// are written with their source text (`1.50` stays `1.50`). Also takes the
+/// parsing options of `loads`; see `text_options`.
+#[pyfunction(name = "to_json")]
+#[pyo3(signature = (s, indent=Some(2), *, keep_float_text=false, **kwargs))]
+fn py_to_json(
+    py: Python<'_>,
+    s: &str,
+    indent: Option<usize>,
+    keep_float_text: bool,
+    kwargs: Option<&Bound<'_, PyDict>>,
+) -> PyResult<String> {
+    let mut opts = text_options(py, kwargs, "to_json")?;
+    opts.keep_float_text |= keep_float_text;
+    Ok(crate::to_json_with(s, indent, &opts)?)
+}
+
+/// Parse one value as written after `key:`, e.g. to validate a form field.
+#[pyfunction(name = "parse_scalar")]
+fn py_parse_scalar(py: Python<'_>, raw: &str) -> PyResult<PyObject> {
+    value_to_pyobj(py, &crate::parse_scalar(raw)?, &ToPyOptions::default())
+}
+
+/// Parse STC and return canonical JSON text, for stable hashing; see
+/// `canonical_json`.
+#[pyfunction(name = "canonical_json")]
+fn py_canonical_json(s: &str) -> PyResult<String> {
+    Ok(crate::canonical_json(s)?)
+}
+
+/// Convert STC whose top level is a list to JSON Lines, one item per line.
+/// With `fp`, each line is passed to `fp.write()` as soon as its item is
+/// finalized and `None` is returned; otherwise the text is returned.
+#[pyfunction(name = "to_jsonl")]
+#[pyo3(signature = (s, fp=None, **kwargs))]
+fn py_to_jsonl(py: Python<'_>, s: &str, fp: Option<&Bound<'_, PyAny>>, kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<PyObject> {
+    let opts = text_options(py, kwargs, "to_jsonl")?;
+    let Some(fp) = fp else {
+        return Ok(crate::to_jsonl_with(s, &opts)?.into_pyobject(py)?.into_any().unbind());
+    };
+    crate::for_each_jsonl_line(s, &opts, |line: &str| fp.call_method1("write", (line,)).map(drop))?;
+    Ok(py.None())
+}
+
+/// The typed empty collections of `s` (`ports: []int`) as `(path, type)`
+/// pairs; see `empty_type_hints`.
+#[pyfunction(name = "empty_type_hints")]
+#[pyo3(signature = (s, **kwargs))]
+fn py_empty_type_hints(py: Python<'_>, s: &str, kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<Vec<(String, &'static str)>> {
+    let (opts, _, _) = load_options(py, kwargs)?;
+    Ok(crate::empty_type_hints(s, &opts)?)
+}
+
+/// Parse STC and return `(value, spans)`, where `spans` maps the JSON pointer
+/// of each value to its `(line, col, end_line, end_col)`; see `parse_with_spans`.
+#[pyfunction(name = "parse_with_spans")]
+#[pyo3(signature = (s, **kwargs))]
+fn py_parse_with_spans(py: Python<'_>, s: &str, kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<PyObject> {
+    let (opts, to_py, _) = load_options(py, kwargs)?;
+    let (val, spans) = crate::parse_with_spans_raw(s, &opts)?;
+    let spans: HashMap<String, (usize, usize, usize, usize)> =
+        spans.into_iter().map(|(p, s)| (p, (s.line, s.col, s.end_line, s.end_col))).collect();
+    let pair = (value_to_pyobj(py, &val, &to_py)?, spans);
+    Ok(pair.into_pyobject(py)?.into_any().unbind())
+}
+
+/// Parse STC and return it as TOML text. Raises `ValueError` for data TOML
+/// cannot hold, such as arrays mixing value types. Takes the parsing options
+/// of `loads`; see `text_options`.
+#[pyfunction(name = "to_toml")]
+#[pyo3(signature = (s, **kwargs))]
+fn py_to_toml(py: Python<'_>, s: &str, kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<String> {
+    Ok(crate::to_toml_with(s, &text_options(py, kwargs, "to_toml")?)?)
+}
+
+#[pymodule]
+fn stc_rust(py: Python<'_>, m: &Bound<PyModule>) -> PyResult<()> {
+    let parse_error = py.get_type::<STCParseError>();
+    // Class-level defaults, so errors raised without position info still have the attributes.
+    for attr in ["lineno", "colno", "kind"] {
+        parse_error.setattr(attr, py.None())?;
+    }
+    m.add("STCParseError", parse_error)?;
+    m.add_function(wrap_pyfunction!(loads, m)?)?;
+    m.add_function(wrap_pyfunction!(load_file, m)?)?;
+    m.add_function(wrap_pyfunction!(try_loads, m)?)?;
+    m.add_function(wrap_pyfunction!(loads_into, m)?)?;
+    m.add_function(wrap_pyfunction!(loads_json_str, m)?)?;
+    m.add_function(wrap_pyfunction!(loads_all, m)?)?;
+    m.add_function(wrap_pyfunction!(py_to_json, m)?)?;
+    m.add_function(wrap_pyfunction!(py_to_jsonl, m)?)?;
+    m.add_function(wrap_pyfunction!(py_canonical_json, m)?)?;
+    m.add_function(wrap_pyfunction!(py_to_toml, m)?)?;
+    m.add_function(wrap_pyfunction!(py_empty_type_hints, m)?)?;
+    m.add_function(wrap_pyfunction!(py_parse_with_spans, m)?)?;
+    m.add_function(wrap_pyfunction!(py_parse_scalar, m)?)?;
+    m.add_function(wrap_pyfunction!(dumps::dumps, m)?)?;
+    m.add_function(wrap_pyfunction!(dumps::dump, m)?)?;
+    m.add_function(wrap_pyfunction!(dumps::min_fence, m)?)?;
+    m.add_function(wrap_pyfunction!(gzip::gunzip, m)?)?;
+    m.add_function(wrap_pyfunction!(lint::py_lint, m)?)?;
+    m.add_function(wrap_pyfunction!(lint::py_validate, m)?)?;
+    m.add_function(wrap_pyfunction!(query::py_values_of_type, m)?)?;
+    m.add_function(wrap_pyfunction!(query::iter_items, m)?)?;
+    m.add_function(wrap_pyfunction!(query::set_at, m)?)?;
+    m.add_function(wrap_pyfunction!(merge::merge, m)?)?;
+    m.add_function(wrap_pyfunction!(cache::clear_cache, m)?)?;
+    m.add_function(wrap_pyfunction!(cache::cache_info, m)?)?;
+    m.add_class::<StcView>()?;
+    m.add_class::<reader::StcReader>()?;
+    // `parse` is the same function object as `loads`, for callers used to that name
+    m.add("parse", m.getattr("loads")?)?;
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,260 @@
+//! Helpers for looking up values by their STC key paths.
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3::types::{PyDict, PyList, PyString, PyTuple};
+use std::borrow::Cow;
+
+use serde_json::Value;
+
+use crate::{base64, parse_stc, StcError};
+use crate::{decode_segment, err, key_segment, parse_key, ErrorKind, AUTO_INDEX};
+#[cfg(feature = "python")]
+use crate::{value_to_pyobj, ToPyOptions};
+
+/// Join a key path segment onto `prefix` the way STC writes it.
+pub(crate) fn join_path(prefix: &str, piece: &str) -> String {
+    if prefix.is_empty() { piece.to_string() } else { format!("{prefix}.{piece}") }
+}
+
+/// Call `f` with the STC path and value of every leaf under `v`, with dict
+/// keys in sorted order and list items in index order. Leaves are scalars,
+/// bytes and empty lists/dicts.
+pub(crate) fn walk_leaves<'v>(v: &'v Value, prefix: &str, f: &mut impl FnMut(&str, &'v Value)) {
+    match v {
+        Value::Object(obj) if !obj.is_empty() && base64::bytes_of(v).is_none() => {
+            for (k, child) in obj {
+                walk_leaves(child, &join_path(prefix, &key_segment(k)), f);
+            }
+        }
+        Value::Array(arr) if !arr.is_empty() => {
+            for (i, child) in arr.iter().enumerate() {
+                walk_leaves(child, &join_path(prefix, &format!("${i}")), f);
+            }
+        }
+        _ => f(prefix, v),
+    }
+}
+
+/// The value at the STC key `path`, if there is one. `$N` and `$-N` index
+/// lists; a bare `$` names no existing item.
+pub(crate) fn lookup<'v>(v: &'v Value, path: &str) -> Result<Option<&'v Value>, StcError> {
+    let segments = parse_key(path.trim(), None)?;
+    if segments.iter().any(|s| s.as_ref() == AUTO_INDEX) {
+        return Err(err(
+            ErrorKind::InvalidKey,
+            format!("Invalid key: {path}. A bare `$` appends to a list, so it names no existing item."),
+            None,
+        ));
+    }
+    Ok(follow(v, &segments).map(|(_, found)| found))
+}
+
+/// Follow parsed key `segments` through `v`. Returns the value found and its
+/// path with every `$-N` resolved to the `$N` it names.
+pub(crate) fn follow<'v, S: AsRef<str>>(v: &'v Value, segments: &[S]) -> Option<(String, &'v Value)> {
+    let (mut path, mut current) = (String::new(), v);
+    for piece in segments {
+        let piece = piece.as_ref();
+        let (next, segment) = match (current, piece.strip_prefix('$')) {
+            (Value::Array(arr), Some(idx)) => {
+                let i = match idx.strip_prefix('-') {
+                    Some(back) => back.parse::<usize>().ok().filter(|&n| n > 0).and_then(|n| arr.len().checked_sub(n)),
+                    None => idx.parse::<usize>().ok(),
+                }?;
+                (arr.get(i)?, Cow::Owned(format!("${i}")))
+            }
+            (Value::Object(obj), None) => (obj.get(decode_segment(piece).as_ref())?, Cow::Borrowed(piece)),
+            _ => return None,
+        };
+        path = join_path(&path, &segment);
+        current = next;
+    }
+    Some((path, current))
+}
+
+/// Fail with one error naming every path of `required` missing from `v`.
+pub(crate) fn check_required(v: &Value, required: &[String]) -> Result<(), StcError> {
+    let mut missing = Vec::new();
+    for path in required {
+        if lookup(v, path)?.is_none() {
+            missing.push(format!("`{}`", path.trim()));
+        }
+    }
+    if missing.is_empty() {
+        return Ok(());
+    }
+    let noun = if missing.len() == 1 { "key" } else { "keys" };
+    Err(err(ErrorKind::MissingRequired, format!("Missing required {noun}: {}.", missing.join(", ")), None))
+}
+
+fn leaf_kind(v: &Value) -> &'static str {
+    match v {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(n) if n.is_f64() => "float",
+        Value::Number(_) => "int",
+        Value::String(_) => "string",
+        Value::Array(_) => "list",
+        Value::Object(_) if base64::bytes_of(v).is_some() => "bytes",
+        Value::Object(_) => "dict",
+    }
+}
+
+const LEAF_KINDS: [&str; 7] = ["string", "int", "float", "bool", "bytes", "list", "dict"];
+
+/// Parse `input` and return the `(path, value)` of every leaf of type `kind`:
+/// one of `"string"`, `"int"`, `"float"`, `"bool"`, `"bytes"`, or
+/// `"list"`/`"dict"` for empty collections. Leaves come in the order of
+/// `walk_leaves`: sorted by key, not by where they are written.
+pub fn values_of_type(input: &str, kind: &str) -> Result<Vec<(String, Value)>, StcError> {
+    if !LEAF_KINDS.contains(&kind) {
+        return Err(StcError::InvalidArgument(format!(
+            "Unknown value type `{kind}`; expected one of {}.",
+            LEAF_KINDS.join(", ")
+        )));
+    }
+    let value = parse_stc(input)?;
+    let mut out = Vec::new();
+    walk_leaves(&value, "", &mut |path, leaf| {
+        if leaf_kind(leaf) == kind {
+            out.push((path.to_string(), leaf.clone()));
+        }
+    });
+    Ok(out)
+}
+
+#[cfg(feature = "python")]
+#[pyfunction(name = "values_of_type")]
+pub(crate) fn py_values_of_type(py: Python<'_>, s: &str, kind: &str) -> PyResult<Vec<(String, PyObject)>> {
+    values_of_type(s, kind)?
+        .into_iter()
+        .map(|(path, v)| Ok((path, value_to_pyobj(py, &v, &ToPyOptions::default())?)))
+        .collect()
+}
+
+#[cfg(feature = "python")]
+fn walk_pyobj<'py>(
+    obj: &Bound<'py, PyAny>,
+    prefix: &str,
+    out: &mut Vec<(String, Bound<'py, PyAny>)>,
+) -> PyResult<()> {
+    if let Ok(d) = obj.downcast::<PyDict>()
+        && !d.is_empty()
+    {
+        for (k, v) in d.iter() {
+            let Ok(k) = k.downcast::<PyString>() else {
+                return Err(StcError::InvalidArgument(format!(
+                    "Dict keys must be strings, found {} under `{prefix}`.",
+                    k.repr()?
+                ))
+                .into());
+            };
+            walk_pyobj(&v, &join_path(prefix, &key_segment(k.to_str()?)), out)?;
+        }
+    } else if (obj.is_instance_of::<PyList>() || obj.is_instance_of::<PyTuple>()) && obj.len()? > 0 {
+        for (i, item) in obj.try_iter()?.enumerate() {
+            walk_pyobj(&item?, &join_path(prefix, &format!("${i}")), out)?;
+        }
+    } else {
+        out.push((prefix.to_string(), obj.clone()));
+    }
+    Ok(())
+}
+
+/// Python `iter_items(obj)`: the `(path, leaf)` pair of every leaf of a parsed
+/// dict or list, in iteration order. Leaves are returned as-is, not copied.
+#[cfg(feature = "python")]
+#[pyfunction]
+pub(crate) fn iter_items<'py>(obj: &Bound<'py, PyAny>) -> PyResult<Vec<(String, Bound<'py, PyAny>)>> {
+    let mut out = Vec::new();
+    walk_pyobj(obj, "", &mut out)?;
+    Ok(out)
+}
+
+/// The list slot `piece` (`$N`, `$-N` or `$`) names in a list of `len` items:
+/// an existing item, or `len` to append.
+#[cfg(feature = "python")]
+fn list_slot(piece: &str, len: usize, here: &str) -> Result<usize, StcError> {
+    let slot = match piece.strip_prefix("$-") {
+        _ if piece == AUTO_INDEX => Some(len),
+        Some(back) => back.parse::<usize>().ok().filter(|&n| n > 0).and_then(|n| len.checked_sub(n)),
+        None => piece[1..].parse::<usize>().ok().filter(|&n| n <= len),
+    };
+    slot.ok_or_else(|| {
+        err(
+            ErrorKind::InvalidKey,
+            format!("List `{here}` has {len} items, so `{piece}` is out of range; only `${len}` can add one."),
+            None,
+        )
+    })
+}
+
+#[cfg(feature = "python")]
+fn set_conflict(here: &str, found: &Bound<'_, PyAny>, piece: &str) -> PyResult<PyErr> {
+    let what = if here.is_empty() { "The document".to_string() } else { format!("Key `{here}`") };
+    let ty = found.get_type().name()?;
+    let msg = if piece.starts_with('$') {
+        format!("{what} is of type {ty}, not a list, so it cannot be indexed with `{piece}`.")
+    } else {
+        format!("{what} is of type {ty}, not a dict, so it cannot have the key `{piece}`.")
+    };
+    Ok(err(ErrorKind::KeyConflict, msg, None).into())
+}
+
+/// Python `set_at(obj, path, value)`: set `value` at the STC key `path` of a
+/// parsed dict or list, in place. Missing dicts and lists along the way are
+/// created, and `$` or `$N` one past the end appends to a list.
+#[cfg(feature = "python")]
+#[pyfunction]
+pub(crate) fn set_at(obj: &Bound<'_, PyAny>, path: &str, value: &Bound<'_, PyAny>) -> PyResult<()> {
+    let segments = parse_key(path.trim(), None)?;
+    let mut current = obj.clone();
+    for (i, piece) in segments.iter().enumerate() {
+        let piece = piece.as_ref();
+        let here = segments[..i].join(".");
+        let child = || -> Bound<'_, PyAny> {
+            match segments.get(i + 1) {
+                Some(next) if next.starts_with('$') => PyList::empty(obj.py()).into_any(),
+                _ => PyDict::new(obj.py()).into_any(),
+            }
+        };
+        let last = i + 1 == segments.len();
+        if piece.starts_with('$') {
+            let Ok(list) = current.downcast::<PyList>() else {
+                return Err(set_conflict(&here, &current, piece)?);
+            };
+            let slot = list_slot(piece, list.len(), &here)?;
+            let next = match (last, slot == list.len()) {
+                (true, true) => return list.append(value),
+                (true, false) => return list.set_item(slot, value),
+                (false, true) => {
+                    let c = child();
+                    list.append(&c)?;
+                    c
+                }
+                (false, false) => list.get_item(slot)?,
+            };
+            current = next;
+        } else {
+            let Ok(dict) = current.downcast::<PyDict>() else {
+                return Err(set_conflict(&here, &current, piece)?);
+            };
+            let key = decode_segment(piece);
+            if last {
+                return dict.set_item(key.as_ref(), value);
+            }
+            let next = match dict.get_item(key.as_ref())? {
+                Some(c) => c,
+                None => {
+                    let c = child();
+                    dict.set_item(key.as_ref(), &c)?;
+                    c
+                }
+            };
+            current = next;
+        }
+    }
+    Ok(())
+}
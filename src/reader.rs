@@ -0,0 +1,169 @@
+//! File-backed reader exposed to Python as `StcReader`.
+
+use std::collections::{HashSet, VecDeque};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+
+use pyo3::prelude::*;
+use serde_json::Value;
+
+use crate::{
+    decode_segment, err, finalize_node, list_dict_conflict, list_slots, value_to_pyobj, ErrorKind, Node, StcError,
+    StcParser, ToPyOptions,
+};
+
+/// Iterates over the top-level `(key, value)` pairs of an STC file, in the
+/// order they are written. A document whose top level is a list yields
+/// `("$0", value)`, `("$1", value)`, ... with the keys it is written with.
+///
+/// The file is fed to the parser line by line, and each pair is yielded as
+/// soon as a line for the next top-level key is read, so only the entry being
+/// parsed is held in memory. The lines of a top-level key must therefore be
+/// together; a key that comes back after another one is an error.
+///
+/// ```python
+/// with StcReader(path) as r:
+///     for key, value in r:
+///         ...
+/// ```
+#[pyclass(module = "stc.stc_rust", unsendable)]
+pub(crate) struct StcReader {
+    path: PathBuf,
+    file: Option<BufReader<File>>,
+    parser: StcParser,
+    // top-level keys already yielded, as key segments
+    taken: HashSet<String>,
+    ready: VecDeque<(String, Value)>,
+    line: String,
+    // An empty file, or one ending in `\n`, has a final empty line, as with `str::split`.
+    trailing_empty: bool,
+    done: bool,
+}
+
+impl StcReader {
+    fn open(&mut self) -> PyResult<()> {
+        if self.file.is_none() && !self.done {
+            self.file = Some(BufReader::new(File::open(&self.path)?));
+        }
+        Ok(())
+    }
+
+    /// Feed the next line, queueing the entries it completes. At the end of
+    /// the file the remaining entries are checked and queued.
+    fn read_line(&mut self) -> PyResult<()> {
+        self.open()?;
+        let Some(file) = self.file.as_mut() else { return Ok(()) };
+        self.line.clear();
+        if file.read_line(&mut self.line)? == 0 {
+            if self.trailing_empty {
+                self.parser.feed_line("")?;
+            }
+            self.file = None;
+            self.done = true;
+            return Ok(self.take_entries(true)?);
+        }
+        self.trailing_empty = self.line.ends_with('\n');
+        self.parser.feed_line(self.line.strip_suffix('\n').unwrap_or(&self.line))?;
+        Ok(self.take_entries(false)?)
+    }
+
+    /// Move the finished top-level entries out of the parser into `ready`:
+    /// all but the one still being written, or all of them once `at_end`.
+    fn take_entries(&mut self, at_end: bool) -> Result<(), StcError> {
+        let parser = &mut self.parser;
+        let current = parser.top_key.clone();
+        if let Some(k) = &current
+            && self.taken.contains(k)
+        {
+            return Err(err(
+                ErrorKind::KeyConflict,
+                format!(
+                    "`{}` is written again after other top-level keys; StcReader yields each top-level key \
+                     once the next one starts, so its lines must be together.",
+                    decode_segment(k)
+                ),
+                Some(parser.ln),
+            ));
+        }
+        if at_end {
+            parser.check_closed()?;
+        }
+        let Node::Map(root, lines) = &mut parser.root else { return Ok(()) };
+        let mut done: Vec<String> = root.keys().filter(|k| at_end || Some(*k) != current.as_ref()).cloned().collect();
+        done.sort();
+        if at_end {
+            // the checks `finish` makes on the whole top level
+            let mut keys: Vec<String> = self.taken.iter().chain(root.keys()).cloned().collect();
+            keys.sort();
+            if keys.first().is_some_and(|k| k.starts_with('$')) {
+                list_slots(&keys, lines, "<root>", parser.opts.max_list_len)?;
+            } else if keys.iter().any(|k| k.starts_with('$')) {
+                return Err(list_dict_conflict("<root>", lines));
+            }
+            // the entry still being written comes last
+            if let Some(pos) = current.as_ref().and_then(|c| done.iter().position(|k| k == c)) {
+                let last = done.remove(pos);
+                done.push(last);
+            }
+        }
+        for k in done {
+            let Some(node) = root.remove(&k) else { continue };
+            let value = finalize_node(node, &k, parser.opts.max_list_len, None)?;
+            self.ready.push_back((decode_segment(&k).into_owned(), value));
+            self.taken.insert(k);
+        }
+        Ok(())
+    }
+}
+
+#[pymethods]
+impl StcReader {
+    #[new]
+    fn new(path: PathBuf) -> Self {
+        StcReader {
+            path,
+            file: None,
+            parser: StcParser::new(),
+            taken: HashSet::new(),
+            ready: VecDeque::new(),
+            line: String::new(),
+            trailing_empty: true,
+            done: false,
+        }
+    }
+
+    fn __enter__(mut slf: PyRefMut<'_, Self>) -> PyResult<PyRefMut<'_, Self>> {
+        slf.open()?;
+        Ok(slf)
+    }
+
+    #[pyo3(signature = (*_args))]
+    fn __exit__(&mut self, _args: &Bound<'_, pyo3::types::PyTuple>) -> bool {
+        self.file = None;
+        self.ready.clear();
+        self.done = true;
+        false
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<(String, PyObject)>> {
+        loop {
+            if let Some((k, v)) = self.ready.pop_front() {
+                return Ok(Some((k, value_to_pyobj(py, &v, &ToPyOptions::default())?)));
+            }
+            if self.done {
+                return Ok(None);
+            }
+            if let Err(e) = self.read_line() {
+                // a parse error ends the iteration
+                self.file = None;
+                self.done = true;
+                return Err(e);
+            }
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! Conversion of parsed documents to TOML, for migrating configs.
+//!
+//! The `toml` crate is not a dependency, so this is a small emitter covering
+//! exactly what a parsed STC document can contain: tables, arrays, strings,
+//! integers, floats and booleans. Base64 blocks become base64 strings.
+
+use std::fmt::Write;
+
+use serde_json::{Map, Value};
+
+use crate::{base64, parse_stc, parse_stc_with, ParseOptions, StcError};
+
+fn ser_err<S: Into<String>>(s: S) -> StcError {
+    StcError::Serialize(s.into())
+}
+
+fn join(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() { key.to_string() } else { format!("{prefix}.{key}") }
+}
+
+fn key(k: &str) -> String {
+    if !k.is_empty() && k.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        k.to_string()
+    } else {
+        string(k)
+    }
+}
+
+fn string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04X}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn kind(v: &Value) -> &'static str {
+    match v {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(n) if n.is_f64() => "float",
+        Value::Number(_) => "int",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "table",
+    }
+}
+
+fn is_table_array(arr: &[Value]) -> bool {
+    !arr.is_empty() && arr.iter().all(Value::is_object)
+}
+
+/// An inline value: a scalar, an inline array or an inline table.
+fn inline(v: &Value, path: &str) -> Result<String, StcError> {
+    Ok(match v {
+        Value::Null => return Err(ser_err(format!("Key `{path}` is null, which TOML cannot represent."))),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => match n.as_f64().filter(|_| n.is_f64()) {
+            // `{:?}` always keeps a `.` or exponent, as TOML floats require.
+            Some(f) => format!("{f:?}"),
+            None => n.to_string(),
+        },
+        Value::String(s) => string(s),
+        Value::Array(arr) => {
+            if let Some(other) = arr.iter().find(|item| kind(item) != kind(&arr[0])) {
+                return Err(ser_err(format!(
+                    "Array `{path}` mixes {} and {} values; the TOML export requires arrays of one type.",
+                    kind(&arr[0]),
+                    kind(other)
+                )));
+            }
+            let items = arr
+                .iter()
+                .enumerate()
+                .map(|(i, item)| inline(item, &format!("{path}.${i}")))
+                .collect::<Result<Vec<_>, _>>()?;
+            format!("[{}]", items.join(", "))
+        }
+        Value::Object(obj) => {
+            let items = obj
+                .iter()
+                .map(|(k, item)| Ok(format!("{} = {}", key(k), inline(item, &join(path, k))?)))
+                .collect::<Result<Vec<_>, StcError>>()?;
+            if items.is_empty() { "{}".to_string() } else { format!("{{ {} }}", items.join(", ")) }
+        }
+    })
+}
+
+struct Emitter {
+    out: String,
+}
+
+impl Emitter {
+    fn header(&mut self, header: &str) {
+        if !self.out.is_empty() {
+            self.out.push('\n');
+        }
+        self.out.push_str(header);
+        self.out.push('\n');
+    }
+
+    /// Write the entries of a table whose header (if any) has been written,
+    /// then its sub-tables. `path` is the STC path and `toml` the TOML key path.
+    fn table(&mut self, obj: &Map<String, Value>, path: &str, toml: &str) -> Result<(), StcError> {
+        for (k, v) in obj {
+            let nested = matches!(v, Value::Object(_)) || matches!(v, Value::Array(a) if is_table_array(a));
+            if !nested {
+                let line = format!("{} = {}\n", key(k), inline(v, &join(path, k))?);
+                self.out.push_str(&line);
+            }
+        }
+        for (k, v) in obj {
+            let child_toml = if toml.is_empty() { key(k) } else { format!("{toml}.{}", key(k)) };
+            match v {
+                Value::Object(child) => {
+                    self.header(&format!("[{child_toml}]"));
+                    self.table(child, &join(path, k), &child_toml)?;
+                }
+                Value::Array(arr) if is_table_array(arr) => {
+                    for (i, item) in arr.iter().enumerate() {
+                        let Value::Object(child) = item else { unreachable!("checked by is_table_array") };
+                        self.header(&format!("[[{child_toml}]]"));
+                        self.table(child, &format!("{}.${i}", join(path, k)), &child_toml)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Serialize a parsed document (whose top level must be an object) as TOML.
+pub fn value_to_toml(v: &Value) -> Result<String, StcError> {
+    let mut v = v.clone();
+    base64::markers_to_strings(&mut v);
+    let Value::Object(obj) = &v else {
+        return Err(ser_err("The top-level value must be a dict."));
+    };
+    let mut em = Emitter { out: String::new() };
+    em.table(obj, "", "")?;
+    Ok(em.out)
+}
+
+/// Parse STC and return it as TOML text.
+pub fn to_toml(input: &str) -> Result<String, StcError> {
+    value_to_toml(&parse_stc(input)?)
+}
+
+/// `to_toml` with explicit parse options.
+pub fn to_toml_with(input: &str, opts: &ParseOptions) -> Result<String, StcError> {
+    value_to_toml(&parse_stc_with(input, opts)?)
+}
@@ -0,0 +1,20 @@
+//! JavaScript bindings through `wasm-bindgen`, enabled by the `wasm` feature.
+//! Build with `--no-default-features --features wasm` for
+//! `wasm32-unknown-unknown`, so the Python bindings are left out.
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{base64, parse_stc};
+
+/// Parse STC into a plain JavaScript value: objects for dicts, arrays for
+/// lists, and base64 blocks as their base64 string. Errors are thrown as
+/// their message, the same text `STCParseError` shows in Python.
+#[wasm_bindgen]
+pub fn parse_to_js(input: &str) -> Result<JsValue, JsValue> {
+    let mut value = parse_stc(input).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    base64::markers_to_strings(&mut value);
+    // plain objects rather than `Map`s, like `JSON.parse`
+    let ser = serde_wasm_bindgen::Serializer::json_compatible();
+    value.serialize(&ser).map_err(JsValue::from)
+}
@@ -0,0 +1,41 @@
+use serde_json::json;
+use stc_rust::{parse_stc, to_string, DocumentBuilder, ErrorKind, StcError};
+
+#[test]
+fn repeated_sets_build_nested_lists_and_dicts() -> Result<(), StcError> {
+    let mut b = DocumentBuilder::new();
+    b.set("app.name", json!("demo"))?;
+    b.set("app.servers.$0.host", json!("a"))?;
+    b.set("app.servers.$0.ports", json!([80, 443]))?;
+    b.set("app.servers.$1", json!({"host": "b", "tags": {}}))?;
+    b.set("app.ratio", json!(0.5))?;
+    let doc = b.build()?;
+    assert_eq!(
+        doc,
+        json!({"app": {
+            "name": "demo",
+            "ratio": 0.5,
+            "servers": [{"host": "a", "ports": [80, 443]}, {"host": "b", "tags": {}}],
+        }})
+    );
+    assert_eq!(parse_stc(&to_string(&doc)?)?, doc);
+    Ok(())
+}
+
+#[test]
+fn builder_applies_the_parser_rules() {
+    let mut b = DocumentBuilder::new();
+    b.set("a", json!(1)).unwrap();
+    let err = b.set("a", json!(2)).err().unwrap();
+    assert!(matches!(err, StcError::Parse { kind: ErrorKind::DuplicateValue, line: Some(2), .. }));
+
+    let mut b = DocumentBuilder::new();
+    b.set("xs", json!([1])).unwrap();
+    assert!(b.set("xs.$", json!(2)).is_err());
+
+    let mut b = DocumentBuilder::new();
+    b.set("xs.$1", json!(1)).unwrap();
+    assert!(matches!(b.build(), Err(StcError::Parse { kind: ErrorKind::InvalidIndex, .. })));
+    assert!(DocumentBuilder::new().set("bad key", json!(1)).is_err());
+    assert!(DocumentBuilder::new().set("a", json!(null)).is_err());
+}
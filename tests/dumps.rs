@@ -0,0 +1,148 @@
+use serde_json::json;
+use stc_rust::{from_json_str, DocumentBuilder, LEXEME_KEY, min_fence_len, parse_file, parse_stc, parse_stc_with, split_to_files, to_string, to_string_with, write_file, DumpOptions, ParseOptions, StcError, SPLIT_ROOT_FILE};
+
+#[test]
+fn to_string_round_trips_through_parse_stc() {
+    let v = json!({"a": {"b": [1, 2.5, true]}, "s": "x\n````\ny", "e": []});
+    assert_eq!(parse_stc(&to_string(&v).unwrap()).unwrap(), v);
+}
+
+#[test]
+fn to_string_rejects_null_and_non_object_root() {
+    assert!(matches!(to_string(&json!({"a": null})), Err(StcError::Serialize(_))));
+    assert!(matches!(to_string_with(&json!([1]), &DumpOptions::default()), Err(StcError::Serialize(_))));
+}
+
+#[test]
+fn collapse_integral_floats_is_opt_in() {
+    let v = json!({"a": 5.0});
+    assert_eq!(to_string(&v).unwrap(), "a: 5.0");
+    let opts = DumpOptions { collapse_integral_floats: true, ..DumpOptions::default() };
+    assert_eq!(to_string_with(&v, &opts).unwrap(), "a: 5");
+}
+
+#[test]
+fn preserved_number_lexemes_stay_out_of_parsed_values() {
+    let src = "a: 1.50\nb: +5\nc: 1e3\nd: 7";
+    let opts = ParseOptions { preserve_number_lexemes: true, keep_float_text: true, ..ParseOptions::default() };
+    assert_eq!(parse_stc_with(src, &opts).unwrap(), json!({"a": 1.5, "b": 5, "c": 1000.0, "d": 7}));
+    assert_eq!(to_string(&parse_stc_with(src, &opts).unwrap()).unwrap(), "a: 1.5\nb: 5\nc: 1000.0\nd: 7");
+    let mut b = DocumentBuilder::new();
+    b.set("a", json!({LEXEME_KEY: "1e3"})).unwrap();
+    assert_eq!(b.build().unwrap(), json!({"a": 1000.0}));
+}
+
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("stc-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn split_to_files_writes_one_file_per_section() {
+    let v = json!({
+        "database": {"host": "db", "port": 5432},
+        "name": "app",
+        "server": {"ports": [80, 443]},
+    });
+    let dir = temp_dir("split");
+    let root = split_to_files(&v, &dir).unwrap();
+    assert_eq!(root, dir.join(SPLIT_ROOT_FILE));
+    assert_eq!(
+        std::fs::read_to_string(&root).unwrap(),
+        "database: !include database.stc\nname: ```\napp\n```\nserver: !include server.stc"
+    );
+    for section in ["database", "server"] {
+        let text = std::fs::read_to_string(dir.join(format!("{section}.stc"))).unwrap();
+        assert_eq!(parse_stc(&text).unwrap(), v[section]);
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn split_files_load_back_through_includes() {
+    let v = json!({"database": {"host": "db", "port": 5432}, "name": "app"});
+    let dir = temp_dir("split-include");
+    let root = split_to_files(&v, &dir).unwrap();
+    assert_eq!(parse_file(&root, &ParseOptions::new().allow_includes(true)).unwrap(), v);
+    let err = parse_file(&root, &ParseOptions::new()).unwrap_err();
+    assert!(err.to_string().contains("Includes are disabled"), "{err}");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn parse_file_names_the_offset_of_invalid_utf8() {
+    let dir = temp_dir("utf8");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("bad.stc");
+    std::fs::write(&path, b"a: 1\nb: \xff\n").unwrap();
+    let err = parse_file(&path, &ParseOptions::new()).unwrap_err();
+    assert!(matches!(&err, StcError::Io(e) if e.kind() == std::io::ErrorKind::InvalidData), "{err}");
+    assert!(err.to_string().ends_with("bad.stc is not valid UTF-8: invalid sequence at byte offset 8"), "{err}");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn write_file_compresses_and_parse_file_reads_it_back() {
+    let v = json!({"a": {"b": [1, 2]}, "s": "text"});
+    let dir = temp_dir("gzip");
+    std::fs::create_dir_all(&dir).unwrap();
+    let (gz, plain) = (dir.join("cfg.stc.gz"), dir.join("cfg.stc"));
+    write_file(&v, &gz, &DumpOptions::default(), true).unwrap();
+    write_file(&v, &plain, &DumpOptions::default(), false).unwrap();
+    assert!(std::fs::read(&gz).unwrap().starts_with(b"\x1f\x8b"));
+    assert_eq!(std::fs::read_to_string(&plain).unwrap(), to_string(&v).unwrap());
+    assert_eq!(parse_file(&gz, &ParseOptions::new()).unwrap(), v);
+    assert_eq!(parse_file(&plain, &ParseOptions::new()).unwrap(), v);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn split_keeps_a_section_named_like_the_root_file_inline() {
+    let v = json!({"index": {"a": 1}, "b": 1});
+    let dir = temp_dir("split-index");
+    let root = split_to_files(&v, &dir).unwrap();
+    assert_eq!(std::fs::read_to_string(&root).unwrap(), "b: 1\nindex.a: 1");
+    assert_eq!(parse_file(&root, &ParseOptions::new().allow_includes(true)).unwrap(), v);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn min_fence_len_is_one_more_than_longest_backtick_run() {
+    assert_eq!(min_fence_len("no backticks"), 3);
+    assert_eq!(min_fence_len("```"), 4);
+    assert_eq!(min_fence_len("````"), 5);
+    assert_eq!(min_fence_len("a ``` b\n`````"), 6);
+}
+
+#[test]
+fn min_fence_len_never_goes_below_three() {
+    assert_eq!(min_fence_len(""), 3);
+    assert_eq!(min_fence_len("`a``b"), 3);
+}
+
+#[test]
+fn from_json_str_converts_nested_objects_and_arrays() {
+    let json = r#"{"db": {"host": "localhost", "replicas": [{"port": 5432}, {"port": 5433}]}, "tags": ["a", "b"], "on": true, "none": []}"#;
+    let stc = from_json_str(json).unwrap();
+    assert!(stc.contains("db.replicas.$1.port: 5433"));
+    assert_eq!(parse_stc(&stc).unwrap(), serde_json::from_str::<serde_json::Value>(json).unwrap());
+}
+
+#[test]
+fn from_json_str_rejects_what_stc_cannot_hold() {
+    let e = from_json_str(r#"{"a": {"b\u0000c": 1}}"#).unwrap_err();
+    assert_eq!(e.to_string(), r#"Key "b\0c" cannot be represented in STC. Keys cannot contain NUL."#);
+    assert!(matches!(from_json_str("[1, 2]"), Err(StcError::Serialize(_))));
+    assert!(matches!(from_json_str("{"), Err(StcError::Deserialize(_))));
+}
+
+#[test]
+fn keys_that_are_not_identifiers_are_quoted_and_round_trip() {
+    let v = json!({"a.b": {"$x": 1, "has space": [{"q\"t": true}]}, "plain": 2, "": 3});
+    let stc = to_string(&v).unwrap();
+    assert!(stc.contains(r#""a.b"."$x": 1"#));
+    assert!(stc.contains(r#""a.b"."has space".$0."q\"t": `true`"#));
+    assert!(stc.contains("plain: 2"));
+    assert_eq!(parse_stc(&stc).unwrap(), v);
+}
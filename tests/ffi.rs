@@ -0,0 +1,38 @@
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+use stc_rust::{stc_free, stc_parse_json};
+
+#[test]
+fn parses_to_json_through_the_c_abi() {
+    let input = CString::new("a.b.$0: 1\na.b.$1: `true`").unwrap();
+    let mut err = ptr::null_mut();
+    let out = unsafe { stc_parse_json(input.as_ptr(), &mut err) };
+    assert!(!out.is_null() && err.is_null());
+    let json = unsafe { CStr::from_ptr(out) }.to_str().unwrap().to_owned();
+    unsafe { stc_free(out) };
+    assert_eq!(json, r#"{"a":{"b":[1,true]}}"#);
+}
+
+#[test]
+fn errors_are_returned_through_out_err() {
+    let input = CString::new("a: hello").unwrap();
+    let mut err = ptr::null_mut();
+    let out = unsafe { stc_parse_json(input.as_ptr(), &mut err) };
+    assert!(out.is_null() && !err.is_null());
+    let msg = unsafe { CStr::from_ptr(err) }.to_str().unwrap().to_owned();
+    unsafe { stc_free(err) };
+    assert!(msg.starts_with("Line 1: Invalid value: hello."), "{msg}");
+    // A null out_err is allowed; the error is then only signalled by null.
+    assert!(unsafe { stc_parse_json(input.as_ptr(), ptr::null_mut()) }.is_null());
+}
+
+#[test]
+fn base64_blocks_are_written_as_strings() {
+    let input = CString::new("a: b64```\naGk=\n```").unwrap();
+    let out = unsafe { stc_parse_json(input.as_ptr(), ptr::null_mut()) };
+    assert!(!out.is_null());
+    let json = unsafe { CStr::from_ptr(out) }.to_str().unwrap().to_owned();
+    unsafe { stc_free(out) };
+    assert_eq!(json, r#"{"a":"aGk="}"#);
+}
@@ -0,0 +1,22 @@
+use serde_json::json;
+use stc_rust::{merge_values, merge_values_with, parse_stc, MergeOptions};
+
+#[test]
+fn merges_nested_objects() {
+    let mut base = parse_stc("a.b: 1\na.c: 2\nx: 0").unwrap();
+    let overlay = parse_stc("a.c: 3\na.d: 4").unwrap();
+    merge_values(&mut base, &overlay);
+    assert_eq!(base, json!({"a": {"b": 1, "c": 3, "d": 4}, "x": 0}));
+}
+
+#[test]
+fn arrays_replace_unless_concatenated() {
+    let overlay = json!({"a": [3]});
+    let mut replaced = json!({"a": [1, 2]});
+    merge_values(&mut replaced, &overlay);
+    assert_eq!(replaced, json!({"a": [3]}));
+
+    let mut concatenated = json!({"a": [1, 2]});
+    merge_values_with(&mut concatenated, &overlay, &MergeOptions { concat_arrays: true });
+    assert_eq!(concatenated, json!({"a": [1, 2, 3]}));
+}
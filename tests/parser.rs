@@ -0,0 +1,201 @@
+use stc_rust::{to_jsonl_with, write_jsonl_with, ErrorKind, parse_stc, parse_stc_all, parse_stc_visit, parse_stc_with, parse_with_spans, to_json_with, ParseOptions, Span, StcError, StcParser};
+
+const DOC: &str = "name: ```\nfirst line\n\n```\nitems.$1: `true`\nitems.$0: 1.5\nnested.a.b: ~~~\n```\n~~~\n";
+
+#[test]
+fn feeding_lines_matches_one_shot_parse() {
+    let mut parser = StcParser::new();
+    for line in DOC.split('\n') {
+        parser.feed_line(line).unwrap();
+    }
+    assert_eq!(parser.finish().unwrap(), parse_stc(DOC).unwrap());
+}
+
+#[test]
+fn feeding_lines_honours_options() {
+    let opts = ParseOptions { keep_trailing_newline: true, ..ParseOptions::default() };
+    let mut parser = StcParser::with_options(opts.clone());
+    for line in DOC.split('\n') {
+        parser.feed_line(line).unwrap();
+    }
+    assert_eq!(parser.finish().unwrap(), parse_stc_with(DOC, &opts).unwrap());
+}
+
+#[test]
+fn errors_surface_on_the_offending_line() {
+    let mut parser = StcParser::new();
+    parser.feed_line("a: 1").unwrap();
+    assert!(matches!(parser.feed_line("b: nope"), Err(StcError::Parse { line: Some(2), .. })));
+}
+
+#[test]
+fn unclosed_block_fails_at_finish() {
+    let mut parser = StcParser::new();
+    parser.feed_line("a: ```").unwrap();
+    parser.feed_line("text").unwrap();
+    assert!(parser.finish().is_err());
+}
+
+#[test]
+fn documents_split_on_separator_lines() {
+    let docs = parse_stc_all(&format!("{DOC}---\na: 1"), &ParseOptions::default()).unwrap();
+    assert_eq!(docs, vec![parse_stc(DOC).unwrap(), serde_json::json!({"a": 1})]);
+}
+
+#[test]
+fn parse_errors_carry_a_kind() {
+    assert!(matches!(parse_stc("a: 1\nb"), Err(StcError::Parse { line: Some(2), kind: ErrorKind::MissingColon, .. })));
+    assert!(matches!(parse_stc("a.$0: 1\na.$2: 1"), Err(StcError::Parse { kind: ErrorKind::InvalidIndex, .. })));
+}
+
+#[test]
+fn options_builder_sets_non_default_options() {
+    let opts = ParseOptions::new().separator("=>").dedent(true).max_list_len(2);
+    assert_eq!(opts.separator.as_deref(), Some("=>"));
+    let doc = "a.$0 => 1\ncode => ```\n    x\n```";
+    assert_eq!(parse_stc_with(doc, &opts).unwrap(), serde_json::json!({"a": [1], "code": "x"}));
+    let err = parse_stc_with("a.$0 => 1\na.$1 => 2\na.$2 => 3", &opts).unwrap_err();
+    assert!(matches!(err, StcError::Parse { kind: ErrorKind::LimitExceeded, .. }));
+}
+
+#[test]
+fn empty_separators_are_rejected_as_invalid_arguments() {
+    let err = parse_stc_with("a: 1", &ParseOptions::new().separator("")).unwrap_err();
+    assert!(matches!(&err, StcError::InvalidArgument(m) if m == "`separator` must not be empty."));
+    let err = parse_stc_with("a: 1", &ParseOptions::new().line_separator("")).unwrap_err();
+    assert!(matches!(&err, StcError::InvalidArgument(m) if m == "`line_separator` must not be empty."));
+    let mut parser = StcParser::with_options(ParseOptions::new().separator(""));
+    assert!(matches!(parser.feed_line("a: 1"), Err(StcError::InvalidArgument(_))));
+}
+
+#[test]
+fn parse_scalar_parses_single_values() {
+    use serde_json::json;
+    use stc_rust::parse_scalar;
+    assert_eq!(parse_scalar("`true`").unwrap(), json!(true));
+    assert_eq!(parse_scalar("42").unwrap(), json!(42));
+    assert_eq!(parse_scalar(" 2.5 ").unwrap(), json!(2.5));
+    assert_eq!(parse_scalar("\"a\\tb\"").unwrap(), json!("a\tb"));
+    assert_eq!(parse_scalar("[]").unwrap(), json!([]));
+    for bad in ["```", "~~~json", "nope"] {
+        let e = parse_scalar(bad).unwrap_err();
+        assert!(matches!(e, StcError::Parse { kind: ErrorKind::InvalidValue, line: None, .. }), "{bad}");
+    }
+}
+
+#[test]
+fn to_json_keeps_float_text_when_asked() {
+    let opts = ParseOptions::new().keep_float_text(true);
+    assert_eq!(to_json_with("x: 1.50", None, &opts).unwrap(), r#"{"x":1.50}"#);
+    assert_eq!(to_json_with("x: 1.50", Some(2), &opts).unwrap(), "{\n  \"x\": 1.50\n}");
+    assert_eq!(to_json_with("x: 1.50", None, &ParseOptions::default()).unwrap(), r#"{"x":1.5}"#);
+}
+
+#[test]
+fn jsonl_output_uses_the_parse_options() {
+    let opts = ParseOptions::new().keep_float_text(true).separator("=");
+    assert_eq!(to_jsonl_with("$0.x = 1.50\n$1 = 2e0", &opts).unwrap(), "{\"x\":1.50}\n2e0\n");
+    let mut out = Vec::new();
+    write_jsonl_with("$0 = +5", &mut out, &ParseOptions::new().preserve_number_lexemes(true).separator("=")).unwrap();
+    assert_eq!(out, b"5\n");
+}
+
+#[test]
+fn preserved_lexemes_keep_the_scientific_ints_typing() {
+    let opts = ParseOptions::new().preserve_number_lexemes(true).scientific_ints(true);
+    assert_eq!(to_json_with("a: +1e3", None, &opts).unwrap(), r#"{"a":1000}"#);
+    assert_eq!(to_json_with("a: +1e3", None, &ParseOptions::new().preserve_number_lexemes(true)).unwrap(), r#"{"a":1000.0}"#);
+}
+
+#[test]
+fn root_can_be_a_list() {
+    assert_eq!(parse_stc("[]").unwrap(), serde_json::json!([]));
+    assert_eq!(parse_stc("{}").unwrap(), serde_json::json!({}));
+    assert_eq!(parse_stc("$0: 1\n$1: 2").unwrap(), serde_json::json!([1, 2]));
+}
+
+#[test]
+fn errors_display_the_python_message() {
+    let e = parse_stc("a: 1\nno colon here").unwrap_err();
+    assert_eq!(e.to_string(), "Line 2: Missing `:`. Line content:\n no colon here");
+    let e = parse_stc("a: ```\nx").unwrap_err();
+    assert_eq!(e.to_string(), "Line 1: Unclosed string block: the input ends at line 2 without its closing ``` fence.");
+}
+
+#[test]
+fn errors_box_as_std_errors() {
+    fn parse(doc: &str) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        Ok(parse_stc(doc)?)
+    }
+    let e = parse("a: nope").unwrap_err();
+    assert!(e.to_string().starts_with("Line 1: Invalid value: nope."));
+    assert!(e.source().is_none());
+    let io = StcError::from(std::io::Error::new(std::io::ErrorKind::NotFound, "gone"));
+    assert_eq!(std::error::Error::source(&io).unwrap().to_string(), "gone");
+}
+
+#[test]
+fn base64_blocks_are_base64_strings_in_json() {
+    let doc = "blob: b64```\naGk=\n```";
+    assert_eq!(stc_rust::to_json(doc, None).unwrap(), r#"{"blob":"aGk="}"#);
+    assert_eq!(parse_stc(doc).unwrap()["blob"][stc_rust::BYTES_KEY], "aGk=");
+}
+
+#[test]
+fn required_paths_are_checked_after_parsing() {
+    let opts = ParseOptions::new().required(["a.b", "c"]);
+    assert!(parse_stc_with("a.b: 1\nc: 2", &opts).is_ok());
+    let e = parse_stc_with("a.b: 1", &opts).unwrap_err();
+    assert!(matches!(e, StcError::Parse { kind: ErrorKind::MissingRequired, line: None, .. }));
+    assert_eq!(e.to_string(), "Missing required key: `c`.");
+}
+
+#[test]
+fn lists_with_a_missing_index_fail_before_any_item_is_placed() {
+    let e = parse_stc("a.$0: 1\na.$2.b: 3\na.$3: 4").unwrap_err();
+    assert!(matches!(e, StcError::Parse { kind: ErrorKind::InvalidIndex, .. }));
+    assert!(e.to_string().contains("List `a` is missing index 1"));
+}
+
+#[test]
+fn fully_specified_lists_never_contain_null() {
+    fn has_null(v: &serde_json::Value) -> bool {
+        match v {
+            serde_json::Value::Null => true,
+            serde_json::Value::Array(a) => a.iter().any(has_null),
+            serde_json::Value::Object(o) => o.values().any(has_null),
+            _ => false,
+        }
+    }
+    let v = parse_stc("a.$2.$2: 5\na.$0: 1\na.$2.$1: 4\na.$1.x: 2\na.$2.$-3: 3").unwrap();
+    assert_eq!(v, serde_json::json!({"a": [1, {"x": 2}, [3, 4, 5]]}));
+    assert!(!has_null(&v));
+}
+
+#[test]
+fn visitor_sees_every_commit_in_source_order() {
+    let doc = "server.port: 80\nitems.$.name: ```\nfirst\n```\n\"a b\".c: []\nitems.$.name: \"second\"\nserver.host: ```\nlocalhost\n```";
+    let mut seen = Vec::new();
+    let v = parse_stc_visit(doc, &ParseOptions::default(), |path, value| seen.push((path.join("."), value.clone()))).unwrap();
+    assert_eq!(
+        seen,
+        [
+            ("server.port".to_string(), serde_json::json!(80)),
+            ("items.$0.name".to_string(), serde_json::json!("first")),
+            ("\"a b\".c".to_string(), serde_json::json!([])),
+            ("items.$1.name".to_string(), serde_json::json!("second")),
+            ("server.host".to_string(), serde_json::json!("localhost")),
+        ]
+    );
+    assert_eq!(v, parse_stc(doc).unwrap());
+}
+
+#[test]
+fn nested_leaf_span_matches_its_source_line() {
+    let doc = "name: ```\nx\n```\nservers.$0.host: `false`\nservers.$1.host: ```\nb\n```";
+    let (v, spans) = parse_with_spans(doc, &ParseOptions::default()).unwrap();
+    assert_eq!(v, parse_stc(doc).unwrap());
+    assert_eq!(spans["/servers/0/host"], Span { line: 4, col: 18, end_line: 4, end_col: 25 });
+    assert_eq!(spans["/servers/1/host"], Span { line: 5, col: 18, end_line: 7, end_col: 4 });
+    assert!(!spans.contains_key("/servers"));
+}
@@ -0,0 +1,86 @@
+//! Property-style round trip: random values through `to_string` then `parse_stc`.
+//!
+//! proptest and quickcheck are not dependencies of this crate, so this uses a
+//! small seeded generator; a failing case prints its seed and document.
+
+use serde_json::{Map, Number, Value};
+use stc_rust::{parse_stc, to_string};
+
+/// xorshift64*, enough to spread cases without a dependency.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+}
+
+fn key(rng: &mut Rng) -> String {
+    const FIRST: &[u8] = b"abcxyzAB_";
+    const REST: &[u8] = b"abcxyz019_";
+    let mut k = String::from(*rng.pick(FIRST) as char);
+    for _ in 0..rng.below(4) {
+        k.push(*rng.pick(REST) as char);
+    }
+    k
+}
+
+/// Strings biased towards backtick runs, tildes, dots, colons and newlines.
+fn string(rng: &mut Rng) -> String {
+    const PIECES: &[&str] = &["`", "``", "```", "````", "~~~", ".", "a.b", ":", "\n", " ", "x", "$0", "{}", "[]", "`true`"];
+    (0..rng.below(8)).map(|_| *rng.pick(PIECES)).collect()
+}
+
+fn scalar(rng: &mut Rng) -> Value {
+    match rng.below(4) {
+        0 => Value::Bool(rng.below(2) == 0),
+        1 => Value::from(rng.next() as i64 >> rng.below(64)),
+        2 => {
+            let f = (rng.next() as i64 >> 11) as f64 / (1u64 << rng.below(60)) as f64;
+            Value::Number(Number::from_f64(f).expect("finite"))
+        }
+        _ => Value::String(string(rng)),
+    }
+}
+
+fn value(rng: &mut Rng, depth: usize) -> Value {
+    if depth == 0 || rng.below(3) == 0 {
+        return scalar(rng);
+    }
+    match rng.below(2) {
+        0 => Value::Array((0..rng.below(4)).map(|_| value(rng, depth - 1)).collect()),
+        _ => object(rng, depth - 1),
+    }
+}
+
+fn object(rng: &mut Rng, depth: usize) -> Value {
+    let mut m = Map::new();
+    for _ in 0..rng.below(4) {
+        m.insert(key(rng), value(rng, depth));
+    }
+    Value::Object(m)
+}
+
+#[test]
+fn random_values_round_trip_through_to_string_and_parse_stc() {
+    for seed in 1..=2000u64 {
+        let mut rng = Rng(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        let v = object(&mut rng, 4);
+        let doc = to_string(&v).unwrap_or_else(|e| panic!("seed {seed}: to_string failed: {e:?}\n{v}"));
+        match parse_stc(&doc) {
+            Ok(back) => assert_eq!(back, v, "seed {seed}: round trip changed the value\n{doc}"),
+            Err(e) => panic!("seed {seed}: parse_stc failed: {e:?}\n{doc}"),
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use std::collections::BTreeMap;
+
+use serde::de::{Deserialize, Deserializer, Error};
+use serde_json::Value;
+use stc_rust::{from_str, StcError};
+
+#[derive(Debug, PartialEq)]
+struct Server {
+    name: String,
+    ports: Vec<u16>,
+}
+
+// Hand-rolled so the tests don't need serde's derive macros.
+impl<'de> Deserialize<'de> for Server {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let mut fields = BTreeMap::<String, Value>::deserialize(d)?;
+        let mut take = |k: &'static str| fields.remove(k).ok_or_else(|| D::Error::missing_field(k));
+        let name = serde_json::from_value(take("name")?).map_err(D::Error::custom)?;
+        let ports = serde_json::from_value(take("ports")?).map_err(D::Error::custom)?;
+        Ok(Server { name, ports })
+    }
+}
+
+#[test]
+fn deserializes_nested_struct_with_vec_field() {
+    let doc = [
+        "server.name: ```",
+        "api",
+        "```",
+        "server.ports.$1: 443",
+        "server.ports.$0: 80",
+    ]
+    .join("\n");
+    let cfg: BTreeMap<String, Server> = from_str(&doc).unwrap();
+    assert_eq!(
+        cfg["server"],
+        Server { name: "api".into(), ports: vec![80, 443] }
+    );
+}
+
+#[test]
+fn type_mismatch_is_a_deserialize_error() {
+    let res: Result<BTreeMap<String, Server>, _> = from_str("server.name: 1\nserver.ports: []");
+    assert!(matches!(res, Err(StcError::Deserialize(_))));
+}
+
+#[test]
+fn invalid_stc_is_a_parse_error() {
+    let res: Result<Value, _> = from_str("a: hello");
+    assert!(matches!(res, Err(StcError::Parse { line: Some(1), .. })));
+}
+
+#[test]
+fn base64_blocks_deserialize_as_their_text() {
+    let doc = "a: b64```\naGk=\n```";
+    let cfg: BTreeMap<String, String> = from_str(doc).unwrap();
+    assert_eq!(cfg["a"], "aGk=");
+    assert_eq!(from_str::<Value>(doc).unwrap(), serde_json::json!({"a": "aGk="}));
+}
@@ -0,0 +1,104 @@
+import pytest
+
+pytestmark = pytest.mark.dumps
+
+def test_dumps_round_trips_nested_structure(loads_fn, dumps_fn):
+    obj = {
+        "a": {"b": 1, "c": [2, {"d": 3.5, "e": True}]},
+        "note": "multi\nline with ``` inside",
+        "empty": "",
+        "lst": [],
+        "obj": {},
+    }
+    assert loads_fn(dumps_fn(obj)) == obj
+
+def test_dumps_preserves_insertion_order_by_default(dumps_fn):
+    assert dumps_fn({"b": 1, "a": 2}) == "b: 1\na: 2"
+
+def test_dumps_sort_keys_is_deterministic(dumps_fn):
+    one = {"b": {"y": 1, "x": 2}, "a": [{"q": 1, "p": 2}]}
+    two = {"a": [{"p": 2, "q": 1}], "b": {"x": 2, "y": 1}}
+    assert dumps_fn(one) != dumps_fn(two)
+    assert dumps_fn(one, sort_keys=True) == dumps_fn(two, sort_keys=True)
+    assert dumps_fn(one, sort_keys=True).splitlines()[0] == "a.$0.p: 2"
+
+@pytest.mark.errors
+def test_dumps_rejects_unrepresentable_values(dumps_fn):
+    for bad in [{"a": None}, {"a\0b": 1}, {"a": float("nan")}, [1, 2]]:
+        with pytest.raises(ValueError):
+            dumps_fn(bad)
+
+def test_dump_compressed_round_trip(tmp_path):
+    from stc import dump, load
+    obj = {"a": {"b": [1, 2]}, "s": "line\n" * 100}
+    path = tmp_path / "cfg.stc.gz"
+    dump(obj, path, compress=True)
+    assert path.read_bytes()[:2] == b"\x1f\x8b"
+    assert load(path) == obj
+    assert load(path, allow_includes=True) == obj
+    plain = tmp_path / "cfg.stc"
+    dump(obj, plain)
+    assert load(str(plain)) == obj
+
+def test_compressed_files_are_standard_gzip(tmp_path):
+    import gzip
+    from stc import dump, dumps, load
+    path = tmp_path / "cfg.stc.gz"
+    dump({"a": 1, "b": "x"}, path, compress=True)
+    assert gzip.decompress(path.read_bytes()).decode("utf-8") == dumps({"a": 1, "b": "x"})
+    other = tmp_path / "other.stc.gz"
+    other.write_bytes(gzip.compress(b"a: 1\n"))
+    assert load(other) == {"a": 1}
+
+@pytest.mark.errors
+def test_dump_compress_rejects_file_like_targets():
+    import io
+    from stc import dump
+    with pytest.raises(TypeError, match="`compress=True` needs a path"):
+        dump({"a": 1}, io.StringIO(), compress=True)
+
+def test_integral_floats_keep_float_type_by_default(loads_fn, dumps_fn):
+    assert dumps_fn({"a": 5.0}) == "a: 5.0"
+    assert type(loads_fn(dumps_fn({"a": 5.0}))["a"]) is float
+
+def test_collapse_integral_floats(loads_fn, dumps_fn):
+    out = dumps_fn({"a": 5.0, "b": 5.5}, collapse_integral_floats=True)
+    assert out == "a: 5\nb: 5.5"
+    assert type(loads_fn(out)["a"]) is int
+
+def test_dump_to_path_round_trips(tmp_path):
+    from stc import dump, load
+    obj = {"srv": {"name": "api", "ports": [80, 443]}, "debug": False}
+    path = tmp_path / "cfg.stc"
+    dump(obj, path)
+    assert load(path) == obj
+
+def test_dump_writes_file_like_incrementally(dumps_fn):
+    from stc import dump
+    chunks = []
+    class Sink:
+        def write(self, s):
+            chunks.append(s)
+    obj = {"a": 1, "b": {"c": "x"}, "d": [True, 2.5]}
+    dump(obj, Sink())
+    assert len(chunks) > 1
+    assert "".join(chunks) == dumps_fn(obj)
+
+def test_dump_io_errors_are_os_errors(tmp_path):
+    from stc import dump
+    with pytest.raises(OSError):
+        dump({"a": 1}, tmp_path / "missing" / "cfg.stc")
+
+def test_dumps_round_trips_bytes(loads_fn, dumps_fn):
+    obj = {"blob": bytes(range(256)), "empty": b""}
+    text = dumps_fn(obj)
+    assert "blob: b64```" in text
+    assert max(len(line) for line in text.split("\n")) <= 76
+    assert loads_fn(text) == obj
+
+@pytest.mark.parametrize("s, n", [("no backticks", 3), ("", 3), ("a ```` b", 5), ("`x``", 3)])
+def test_min_fence(loads_fn, s, n):
+    from stc import min_fence
+    assert min_fence(s) == n
+    fence = "`" * n
+    assert loads_fn(f"a: {fence}\n{s}\n{fence}") == {"a": s}
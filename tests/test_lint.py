@@ -0,0 +1,48 @@
+import pytest
+
+from stc import lint, validate
+
+pytestmark = pytest.mark.lint
+
+def test_near_duplicate_keys_warn_but_parse(loads_fn):
+    doc = "\n".join([
+        "Server.port: 1",
+        "server.port: 2",
+    ])
+    assert loads_fn(doc) == {"Server": {"port": 1}, "server": {"port": 2}}
+    warnings = lint(doc)
+    assert len(warnings) == 1
+    assert "`Server`" in warnings[0] and "`server`" in warnings[0]
+
+def test_nested_near_duplicates_report_full_path():
+    warnings = lint("a.$0.Name: 1\na.$0.name: 2")
+    assert warnings and "`a.$0.Name`" in warnings[0]
+
+def test_distinct_keys_do_not_warn():
+    assert lint("a: 1\nb: 2") == []
+
+def test_validate_returns_none_for_valid_input():
+    assert validate("a.$0: 1\na.$1: `true`") is None
+
+def test_validate_raises_on_invalid_input():
+    with pytest.raises(Exception, match="List `a` is missing index 1"):
+        validate("a.$0: 1\na.$2: 2")
+
+def test_return_warnings_reports_prefix_shadowing(loads_fn):
+    doc = "a: 1\na.b: 2\nc.d: 3\nc: 4\ne: 5"
+    value, warnings = loads_fn(doc, return_warnings=True)
+    assert value == {"a": 1, "c": {"d": 3}, "e": 5}
+    assert warnings == [
+        "Line 2: Key `a` is set both a value and at least one list item / dict attribute: "
+        "first set as a value on line 1; attempted as a container on line 2.",
+        "Line 4: Key `c` is set both a value directly and at least one list item / dict attribute: "
+        "first set as a container on line 3; attempted as a value on line 4.",
+    ]
+    with pytest.raises(Exception, match="Line 2: Key `a` is set both"):
+        loads_fn(doc)
+
+def test_return_warnings_includes_lints_and_keeps_other_errors(loads_fn):
+    assert loads_fn("Server.port: 1\nserver.port: 2", return_warnings=True)[1] == lint("Server.port: 1\nserver.port: 2")
+    assert loads_fn("a: 1", return_warnings=True) == ({"a": 1}, [])
+    with pytest.raises(Exception, match="set at least two values"):
+        loads_fn("a: 1\na: 2", return_warnings=True)
@@ -0,0 +1,123 @@
+import asyncio
+
+import pytest
+
+from stc import load, load_async
+from stc.exceptions import STCParseError
+
+pytestmark = pytest.mark.files
+
+def test_load_accepts_str_and_pathlib_paths(tmp_path):
+    path = tmp_path / "cfg.stc"
+    path.write_text("a.b: 1\n")
+    assert load(path) == load(str(path)) == {"a": {"b": 1}}
+
+def test_load_accepts_backslash_separators(tmp_path):
+    sub = tmp_path / "conf"
+    sub.mkdir()
+    (sub / "cfg.stc").write_text("a: 1")
+    assert load(str(tmp_path) + "\\conf\\cfg.stc") == {"a": 1}
+
+def test_load_async_matches_load(tmp_path):
+    path = tmp_path / "cfg.stc"
+    path.write_text("a.b: 1\nname: ```\nx\n```\n")
+    assert asyncio.run(load_async(path)) == load(path) == {"a": {"b": 1}, "name": "x"}
+    assert asyncio.run(load_async(str(path), impl="python")) == load(path)
+    with open(path) as f:
+        assert asyncio.run(load_async(f)) == load(path)
+
+@pytest.mark.errors
+def test_load_async_raises_like_load(tmp_path):
+    with pytest.raises(FileNotFoundError):
+        asyncio.run(load_async(tmp_path / "nope.stc"))
+
+def test_include_splices_the_other_file(tmp_path):
+    (tmp_path / "conf").mkdir()
+    (tmp_path / "conf" / "db.stc").write_text("host: ```\ndb\n```\nports.$0: 5432\n")
+    (tmp_path / "index.stc").write_text("database: !include conf/db.stc\ndatabase.user: ```\napp\n```\ndebug: `true`\n")
+    assert load(tmp_path / "index.stc", allow_includes=True) == {
+        "database": {"host": "db", "ports": [5432], "user": "app"},
+        "debug": True,
+    }
+
+def test_later_lines_extend_an_included_list(tmp_path):
+    (tmp_path / "x.stc").write_text("l.$0: 1\n")
+    (tmp_path / "index.stc").write_text("x: !include x.stc\nx.l.$1: 2\n")
+    assert load(tmp_path / "index.stc", allow_includes=True) == {"x": {"l": [1, 2]}}
+
+def test_required_keys_are_checked_on_the_whole_document(tmp_path):
+    (tmp_path / "db.stc").write_text("host: 1\n")
+    (tmp_path / "index.stc").write_text("database: !include db.stc\nname: 1\n")
+    required = ["database.host", "name"]
+    assert load(tmp_path / "index.stc", allow_includes=True, required=required) == {"database": {"host": 1}, "name": 1}
+    with pytest.raises(STCParseError, match=r"^Missing required key: `database.port`\.$"):
+        load(tmp_path / "index.stc", allow_includes=True, required=["database.port"])
+
+@pytest.mark.errors
+def test_limits_count_every_included_file(tmp_path):
+    (tmp_path / "db.stc").write_text("a: 1\nb: 2\n")
+    (tmp_path / "index.stc").write_text("database: !include db.stc\nname: 1\n")
+    assert load(tmp_path / "index.stc", allow_includes=True, max_lines=4)["name"] == 1
+    with pytest.raises(STCParseError, match=r"Line 2: Input exceeds max_lines=3\."):
+        load(tmp_path / "index.stc", allow_includes=True, max_lines=3)
+    with pytest.raises(STCParseError, match=r"In included file `db.stc`: Line 2: Input exceeds max_lines=2\."):
+        load(tmp_path / "index.stc", allow_includes=True, max_lines=2)
+    size = len("database: !include db.stc\nname: 1\n") + len("a: 1\nb: 2\n")
+    assert load(tmp_path / "index.stc", allow_includes=True, max_bytes=size)["name"] == 1
+    with pytest.raises(STCParseError, match=r"max_bytes"):
+        load(tmp_path / "index.stc", allow_includes=True, max_bytes=size - 1)
+
+def test_load_async_resolves_includes(tmp_path):
+    (tmp_path / "db.stc").write_text("host: 1\n")
+    (tmp_path / "index.stc").write_text("database: !include db.stc\n")
+    assert asyncio.run(load_async(tmp_path / "index.stc", allow_includes=True)) == {"database": {"host": 1}}
+
+def test_includes_read_gzip_files(tmp_path):
+    import gzip
+    (tmp_path / "db.stc.gz").write_bytes(gzip.compress(b"host: 1\n"))
+    (tmp_path / "index.stc.gz").write_bytes(gzip.compress(b"database: !include db.stc.gz\n"))
+    assert load(tmp_path / "index.stc.gz", allow_includes=True) == {"database": {"host": 1}}
+
+@pytest.mark.errors
+def test_includes_report_invalid_utf8_like_load(tmp_path):
+    (tmp_path / "bad.stc").write_bytes(b"a: 1\nb: \xff\n")
+    (tmp_path / "index.stc").write_text("x: !include bad.stc")
+    for path in ["bad.stc", "index.stc"]:
+        with pytest.raises(UnicodeDecodeError, match="invalid sequence at byte offset 8") as exc:
+            load(tmp_path / path, allow_includes=True)
+        assert exc.value.start == 8
+
+@pytest.mark.errors
+def test_includes_are_disabled_by_default(tmp_path):
+    (tmp_path / "db.stc").write_text("host: 1")
+    (tmp_path / "index.stc").write_text("database: !include db.stc")
+    with pytest.raises(STCParseError, match=r"Line 1: Invalid value: !include db.stc\. Includes are disabled"):
+        load(tmp_path / "index.stc")
+
+@pytest.mark.errors
+def test_include_cycle_is_reported(tmp_path):
+    (tmp_path / "a.stc").write_text("x: 1\nb: !include b.stc")
+    (tmp_path / "b.stc").write_text("a: !include a.stc")
+    with pytest.raises(STCParseError, match=r"Include cycle: a\.stc -> b\.stc -> a\.stc\.") as exc:
+        load(tmp_path / "a.stc", allow_includes=True)
+    assert exc.value.kind == "include_cycle"
+    assert exc.value.lineno == 2
+
+@pytest.mark.errors
+def test_missing_file_is_not_a_parse_error(tmp_path):
+    with pytest.raises(FileNotFoundError) as exc:
+        load(tmp_path / "nope.stc")
+    assert not isinstance(exc.value, STCParseError)
+
+@pytest.mark.errors
+def test_directory_is_rejected(tmp_path):
+    with pytest.raises(IsADirectoryError):
+        load(tmp_path)
+
+@pytest.mark.errors
+def test_invalid_utf8_reports_byte_offset(tmp_path):
+    path = tmp_path / "bad.stc"
+    path.write_bytes(b"a: 1\nb: \xff\n")
+    with pytest.raises(UnicodeDecodeError, match="invalid sequence at byte offset 8") as exc:
+        load(path)
+    assert exc.value.start == 8
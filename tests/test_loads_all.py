@@ -0,0 +1,32 @@
+import pytest
+
+from stc import loads_all
+
+pytestmark = pytest.mark.documents
+
+def test_two_documents_with_a_string_block(make_fence):
+    first = "\n".join(["name: 1", make_fence("line one\nline two", key="note")])
+    docs = loads_all("\n".join([first, "---", "name: 2"]))
+    assert docs == [{"name": 1, "note": "line one\nline two"}, {"name": 2}]
+
+def test_separator_inside_string_block_is_an_error():
+    with pytest.raises(Exception, match="Line 3: Document separator `---` inside the string block starting at line 2"):
+        loads_all("a: 1\nb: ```\n---\n```")
+
+def test_errors_report_lines_in_the_whole_stream():
+    with pytest.raises(Exception, match="Line 3: "):
+        loads_all("a: 1\n---\nb: hello")
+
+def test_options_apply_to_every_document():
+    docs = loads_all("a:\n---\nb:", empty_values="null", readonly=True)
+    assert [dict(d) for d in docs] == [{"a": None}, {"b": None}]
+    with pytest.raises(KeyError):
+        docs[1]["c"]
+
+def test_required_keys_are_checked_per_document():
+    with pytest.raises(Exception, match="Missing required key: `a`."):
+        loads_all("a: 1\n---\nb: 2", required=["a"])
+
+def test_unsupported_options_are_rejected():
+    with pytest.raises(ValueError, match="`cache` cannot be used with `loads_all`."):
+        loads_all("a: 1", cache=True)
@@ -6,6 +6,16 @@ def test_empty(loads_fn):
     doc = "{}"
     assert loads_fn(doc) == {}
 
+def test_empty_top_level_list(loads_fn):
+    assert loads_fn("[]") == []
+    assert loads_fn("\n  [] \n") == []
+    with pytest.raises(Exception, match=r"Line 1: Missing `:`. Line content:\n \[\]"):
+        loads_fn("[]\na: 1")
+
+def test_top_level_list_from_indices(loads_fn):
+    assert loads_fn("$0: 1\n$1: 2") == [1, 2]
+    assert loads_fn("$1.a: 2\n$0: `true`") == [True, {"a": 2}]
+
 def test_int_scalar(loads_fn):
     doc = "a: 10"
     assert loads_fn(doc) == {"a": 10}
@@ -44,3 +54,163 @@ def test_string_preserves_symbols_and_newlines(loads_fn, make_fence):
     raw = "Multi-line with <tags> & symbols.\n\nTrailing newline? Yes.\n"
     doc = make_fence(raw, key="note")
     assert loads_fn(doc) == {"note": raw}
+
+def test_numbers_are_coerced_by_default(loads_fn):
+    assert loads_fn("zip: 1234\nversion: 1.0") == {"zip": 1234, "version": 1.0}
+
+def test_strict_types_rejects_bare_numbers(loads_fn, make_fence):
+    with pytest.raises(Exception, match="Bare numbers are not coerced"):
+        loads_fn("version: 1.0", coerce_numbers=False)
+    doc = make_fence("01234", key="zip")
+    assert loads_fn(doc, coerce_numbers=False) == {"zip": "01234"}
+
+def test_strict_types_accepts_backticked_numbers(loads_fn):
+    out = loads_fn("port: `8080`\nratio: `0.5`", coerce_numbers=False)
+    assert out == {"port": 8080, "ratio": 0.5}
+
+def test_satisfied_type_annotations(loads_fn, make_fence):
+    doc = "\n".join(["server.port(int): 8080", "ratio(float): 0.5", "on(bool): `true`", make_fence("01234", key="zip(str)")])
+    assert loads_fn(doc) == {"server": {"port": 8080}, "ratio": 0.5, "on": True, "zip": "01234"}
+
+def test_violated_type_annotation(loads_fn):
+    with pytest.raises(Exception, match="Line 1: Key `port` is annotated `int` but its value is a float"):
+        loads_fn("port(int): 80.5")
+    with pytest.raises(Exception, match="Unknown type annotation `num`"):
+        loads_fn("port(num): 80")
+
+def test_use_decimal_keeps_the_written_digits(loads_fn):
+    from decimal import Decimal
+    out = loads_fn("a: 0.1\nb: 1.50\nc: 2", use_decimal=True)
+    assert out == {"a": Decimal("0.1"), "b": Decimal("1.50"), "c": 2}
+    assert str(out["b"]) == "1.50"
+    assert type(out["c"]) is int
+    assert loads_fn("a: 0.1")["a"] == 0.1
+
+def test_tabs_in_indentation_and_around_the_colon_are_ignored(loads_fn):
+    assert loads_fn("\ta: 1\n\t\tb.c: 2") == {"a": 1, "b": {"c": 2}}
+    assert loads_fn("a\t: 1\nb:\t2\nc\t:\t3") == {"a": 1, "b": 2, "c": 3}
+
+def test_tab_inside_a_key_segment_is_rejected(loads_fn):
+    with pytest.raises(Exception, match=r"Invalid key: a\\tb. Key segments cannot contain tabs"):
+        loads_fn("a\tb: 1")
+    with pytest.raises(Exception, match="cannot contain whitespace"):
+        loads_fn("a. b: 1")
+
+@pytest.mark.parametrize("sep", [":", "=", "=>"])
+def test_configurable_separator(loads_fn, sep):
+    doc = "\n".join([
+        f"server.port {sep} 8080",
+        f"server.tags.$0{sep}`true`",
+        f"note {sep} ```",
+        "a: b = c => d",
+        "```",
+    ])
+    assert loads_fn(doc, separator=sep) == {
+        "server": {"port": 8080, "tags": [True]},
+        "note": "a: b = c => d",
+    }
+
+def test_tab_separator_reads_tsv_lines(loads_fn):
+    doc = "\n".join([
+        "name\t\"a\tb\"",
+        "\tserver.port\t8080",
+        "ratio\t\t0.5",
+        "url\t\"http://x:80\"",
+        "script\t```",
+        "\tindented: yes",
+        "```",
+    ])
+    assert loads_fn(doc, separator="\t") == {
+        "name": "a\tb",
+        "server": {"port": 8080},
+        "ratio": 0.5,
+        "url": "http://x:80",
+        "script": "\tindented: yes",
+    }
+    with pytest.raises(Exception, match="Line 1: Missing `\t`"):
+        loads_fn("a: 1", separator="\t")
+
+@pytest.mark.errors
+def test_separator_is_used_in_messages(loads_fn):
+    with pytest.raises(Exception, match="Line 1: Missing `=`"):
+        loads_fn("a: 1", separator="=")
+    with pytest.raises(Exception, match="Only the first `=` separates key and value"):
+        loads_fn("a = x=y", separator="=")
+    with pytest.raises(ValueError, match="must not be empty"):
+        loads_fn("a: 1", separator="")
+
+def test_line_separator_parses_a_one_line_document(loads_fn):
+    assert loads_fn("a: 1; b: 2", line_separator=";") == {"a": 1, "b": 2}
+    assert loads_fn("a.$: 1;a.$: `true`;b: \"x y\";", line_separator=";") == {"a": [1, True], "b": "x y"}
+    assert loads_fn("a: 1", line_separator="\n") == {"a": 1}
+
+@pytest.mark.errors
+def test_line_separator_rejects_string_blocks(loads_fn):
+    with pytest.raises(Exception, match=r"^Line 2: Invalid value: ```\. String blocks need newline-separated lines, "
+                       r"so they cannot be used with line_separator=\";\"\.$") as exc:
+        loads_fn("a: 1; b: ```; x; ```", line_separator=";")
+    assert (exc.value.kind, exc.value.colno) == ("invalid_value", 5)
+    with pytest.raises(Exception, match=r"^Line 1: Invalid value: 1\\u\{a\}b: 2\. "):
+        loads_fn("a: 1\nb: 2", line_separator=";")
+    with pytest.raises(ValueError, match="must not be empty"):
+        loads_fn("a: 1", line_separator="")
+
+@pytest.mark.errors
+def test_loads_rejects_unknown_or_mistyped_options(loads_fn):
+    with pytest.raises(TypeError, match="unexpected keyword argument `dednet`"):
+        loads_fn("a: 1", dedent=True, dednet=True)
+    with pytest.raises(TypeError, match="argument `max_lines`"):
+        loads_fn("a: 1", max_lines="ten")
+
+def test_scientific_notation_is_a_float_by_default(loads_fn):
+    out = loads_fn("a: 1e3\nb: 1.5e2\nc: 1e30")
+    assert out == {"a": 1000.0, "b": 150.0, "c": 1e30}
+    assert all(type(v) is float for v in out.values())
+
+def test_scientific_ints_types_integral_values_as_int(loads_fn):
+    doc = "a: 1e3\nb: 1.5e2\nc: -25E-1\nd: 1e30\ne: 1.25e1\nf: 9223372036854775807e0\ng: 42"
+    out = loads_fn(doc, scientific_ints=True)
+    assert out == {"a": 1000, "b": 150, "c": -2.5, "d": 1e30, "e": 12.5, "f": 9223372036854775807, "g": 42}
+    assert [type(out[k]) for k in "abcdefg"] == [int, int, float, float, float, int, int]
+
+def test_float_to_int_when_whole(loads_fn):
+    doc = "a: 2.0\nb: 2.5\nc: -3.00\nd: 1.5e2\ne: 1e30"
+    default = loads_fn(doc)
+    assert default == {"a": 2.0, "b": 2.5, "c": -3.0, "d": 150.0, "e": 1e30}
+    assert all(type(v) is float for v in default.values())
+    out = loads_fn(doc, float_to_int_when_whole=True)
+    assert out == {"a": 2, "b": 2.5, "c": -3, "d": 150, "e": 1e30}
+    assert [type(out[k]) for k in "abcde"] == [int, float, int, int, float]
+    # scientific_ints only covers scientific notation
+    assert type(loads_fn("a: 2.0", scientific_ints=True)["a"]) is float
+
+def test_parse_scalar_checks_one_value():
+    from stc.stc_rust import parse_scalar
+    assert parse_scalar("`true`") is True
+    assert parse_scalar("42") == 42 and parse_scalar("3.14") == 3.14
+    with pytest.raises(Exception, match="string block needs content lines"):
+        parse_scalar("```")
+
+def test_extension_exports_parse_alias_and_version():
+    import re
+    from stc import stc_rust
+    assert stc_rust.parse is stc_rust.loads
+    assert stc_rust.parse("a.b: 1") == {"a": {"b": 1}}
+    assert re.fullmatch(r"\d+\.\d+\.\d+.*", stc_rust.__version__)
+
+@pytest.mark.parametrize("impl", ["rust", "python"])
+def test_comment_lines_are_ignored(impl):
+    from stc import loads
+    doc = "# settings\na: 1\n  # indented comment\nb: ```\n# kept in the block\n```"
+    assert loads(doc, impl=impl) == {"a": 1, "b": "# kept in the block"}
+
+@pytest.mark.parametrize("impl", ["rust", "python"])
+@pytest.mark.parametrize("doc", ["# header\n{}", "{}\n# trailer", "\n# a\n  {}  \n\n# b\n", "# only comments", "# one\n\n  # two\n"])
+def test_empty_document_with_comments(impl, doc):
+    from stc import loads
+    assert loads(doc, impl=impl) == {}
+
+def test_comment_lines_count_toward_line_numbers(loads_fn):
+    with pytest.raises(Exception, match=r"^Line 3: Missing `:`") as exc:
+        loads_fn("# one\na: 1\nnope")
+    assert exc.value.lineno == 3
@@ -0,0 +1,37 @@
+import pytest
+
+from stc.stc_rust import cache_info, clear_cache
+
+pytestmark = pytest.mark.cache
+
+DOC = "a.b: 1\na.c.$0: ```\nx\n```"
+
+def test_cached_loads_returns_equal_results(loads_fn):
+    clear_cache()
+    first = loads_fn(DOC, cache=True)
+    second = loads_fn(DOC, cache=True)
+    assert first == second == {"a": {"b": 1, "c": ["x"]}}
+    # callers get independent objects, not a shared cached dict
+    assert first is not second
+    assert cache_info() == {"hits": 1, "misses": 1, "size": 1}
+
+def test_clear_cache_forces_reparse(loads_fn):
+    clear_cache()
+    loads_fn(DOC, cache=True)
+    clear_cache()
+    loads_fn(DOC, cache=True)
+    assert cache_info()["misses"] == 1 and cache_info()["hits"] == 0
+
+def test_cache_key_includes_options(loads_fn):
+    clear_cache()
+    doc = "a: ```\nx\n\n```"
+    assert loads_fn(doc, cache=True) == {"a": "x\n"}
+    assert loads_fn(doc, cache=True, keep_trailing_newline=True) == {"a": "x\n\n"}
+
+@pytest.mark.errors
+def test_errors_are_not_cached(loads_fn):
+    clear_cache()
+    for _ in range(2):
+        with pytest.raises(Exception):
+            loads_fn("a: nope", cache=True)
+    assert cache_info()["size"] == 0
@@ -31,3 +31,152 @@ def test_duplicate_leaf_path_is_invalid(loads_fn):
     doc = "a.b: 1\na.b: 2"
     with pytest.raises(Exception):
         loads_fn(doc)
+
+def test_duplicate_keys_error_is_default(loads_fn):
+    doc = "a.b: 1\na.b: 2"
+    with pytest.raises(Exception, match="set at least two values"):
+        loads_fn(doc, duplicate_keys="error")
+
+def test_equal_and_differing_duplicates_have_different_messages(loads_fn):
+    with pytest.raises(Exception, match=r"^Line 2: Key `a.b` assigned the same value twice; remove the duplicate line\.$") as exc:
+        loads_fn("a.b: 1\na.b: 1")
+    assert exc.value.kind == "duplicate_value"
+    with pytest.raises(Exception, match="assigned the same value twice"):
+        loads_fn("a: ```\nhi\n```\na: ```\nhi\n```")
+    with pytest.raises(Exception, match=r"Key `a.b` is set at least two values \"Int\(1\)\" \| \"Int\(2\)\"\."):
+        loads_fn("a.b: 1\na.b: 2")
+    with pytest.raises(Exception, match="is set at least two values"):
+        loads_fn("a: 1\na: 1.0")
+
+def test_duplicate_keys_last_wins(loads_fn):
+    doc = "a.b: 1\na.c: 0\na.b: ```\ntwo\n```"
+    assert loads_fn(doc, duplicate_keys="last_wins") == {"a": {"b": "two", "c": 0}}
+
+def test_duplicate_keys_first_wins(loads_fn):
+    doc = "a.b: 1\na.b: 2"
+    assert loads_fn(doc, duplicate_keys="first_wins") == {"a": {"b": 1}}
+
+@pytest.mark.errors
+def test_duplicate_keys_policy_does_not_hide_container_conflicts(loads_fn):
+    for policy in ["last_wins", "first_wins"]:
+        with pytest.raises(Exception, match="list item / dict attribute"):
+            loads_fn("a.b: 1\na: 2", duplicate_keys=policy)
+        with pytest.raises(Exception, match="list item / dict attribute"):
+            loads_fn("a: 2\na.b: 1", duplicate_keys=policy)
+
+@pytest.mark.errors
+def test_container_conflicts_cite_the_first_line(loads_fn):
+    with pytest.raises(Exception, match=r"^Line 3: Key `a` is set both a value and at least one list item / dict attribute: "
+                       r"first set as a value on line 1; attempted as a container on line 3\.$") as exc:
+        loads_fn("a: 1\nb: 2\na.x.y: 3")
+    assert exc.value.kind == "key_conflict"
+    with pytest.raises(Exception, match=r"^Line 4: Key `a.x` is set both a value directly and at least one list item / dict attribute: "
+                       r"first set as a container on line 2; attempted as a value on line 4\.$"):
+        loads_fn("b: 2\na.x.y: 3\na.z: 1\na.x: 1")
+    with pytest.raises(Exception, match=r"first set as a value on line 1; attempted as a container on line 2"):
+        loads_fn("items.$0: 1\nitems.$0.name: 2")
+
+@pytest.mark.errors
+def test_empty_collections_are_sealed_by_default(loads_fn):
+    with pytest.raises(Exception, match=r"^Line 2: Key `a` is set both a value and at least one list item / dict attribute") as exc:
+        loads_fn("a: {}\na.b: 1")
+    assert exc.value.kind == "key_conflict"
+    with pytest.raises(Exception, match="first set as a value on line 1; attempted as a container on line 2"):
+        loads_fn("items: []\nitems.$0: 1")
+
+def test_open_empty_collections(loads_fn):
+    doc = "a: {}\na.b: 1\nitems: []\nitems.$0: 1\nitems.$1: 2\nnone: {}"
+    assert loads_fn(doc, open_empty_collections=True) == {"a": {"b": 1}, "items": [1, 2], "none": {}}
+    assert loads_fn("items: []\nitems.$: 1\nitems.$: 2", open_empty_collections=True) == {"items": [1, 2]}
+
+@pytest.mark.errors
+def test_open_empty_collections_keep_their_kind(loads_fn):
+    with pytest.raises(Exception, match="list item / dict attribute"):
+        loads_fn("a: {}\na.$0: 1", open_empty_collections=True)
+    with pytest.raises(Exception, match="list item / dict attribute"):
+        loads_fn("a: []\na.b: 1", open_empty_collections=True)
+    with pytest.raises(Exception, match="first set as a container on line 1; attempted as a value on line 3"):
+        loads_fn("a: {}\na.b: 1\na: {}", open_empty_collections=True)
+
+@pytest.mark.errors
+def test_duplicate_keys_unknown_policy(loads_fn):
+    with pytest.raises(ValueError):
+        loads_fn("a: 1", duplicate_keys="merge")
+
+def test_dict_class_builds_every_mapping(loads_fn):
+    from collections import OrderedDict
+    out = loads_fn("b.y: 1\nb.x: 2\na.$0.k: 3", dict_class=OrderedDict)
+    assert type(out) is OrderedDict
+    assert list(out) == ["a", "b"] and list(out["b"]) == ["x", "y"]
+    assert type(out["a"][0]) is OrderedDict
+    assert type(loads_fn("a.b: 1", dict_class=dict)["a"]) is dict
+
+def test_dict_class_may_be_any_mapping_factory(loads_fn):
+    class Recording(dict):
+        def __setitem__(self, key, value):
+            super().__setitem__(key.upper(), value)
+    assert loads_fn("a.b: 1", dict_class=Recording) == {"A": {"B": 1}}
+
+@pytest.mark.errors
+def test_dict_class_conflicts_with_readonly(loads_fn):
+    from collections import OrderedDict
+    with pytest.raises(ValueError, match="cannot be combined"):
+        loads_fn("a: 1", dict_class=OrderedDict, readonly=True)
+
+def test_intern_keys_shares_equal_keys(loads_fn):
+    doc = "\n".join(f"jobs.${i}.status: {i}" for i in range(3))
+    plain, interned = loads_fn(doc), loads_fn(doc, intern_keys=True)
+    assert interned == plain
+    keys = [next(iter(job)) for job in interned["jobs"]]
+    assert keys[0] is keys[1] is keys[2]
+    assert interned == loads_fn(doc, intern_keys=True, dict_class=dict)
+
+@pytest.mark.parametrize("key", ["a.b", "$x", "has space", 'q"uote', "colon: here", "é"])
+def test_quoted_keys_round_trip(loads_fn, dumps_fn, key):
+    obj = {key: {"inner": 1}, "list": [{key: 2}]}
+    assert loads_fn(dumps_fn(obj)) == obj
+
+def test_quoted_key_segments(loads_fn):
+    doc = '"a.b".c: 1\n"a.b"."$x": 2\nplain."ok": 3\n"$0": 4'
+    assert loads_fn(doc) == {"a.b": {"c": 1, "$x": 2}, "plain": {"ok": 3}, "$0": 4}
+    with pytest.raises(Exception, match=r"Quoted key segment `\"a` is not closed"):
+        loads_fn('"a: 1')
+
+@pytest.mark.errors
+def test_text_after_a_closed_quoted_segment_is_named(loads_fn):
+    with pytest.raises(Exception) as e:
+        loads_fn('"a"b": 1')
+    assert str(e.value) == 'Line 1: Invalid key: "a"b". Quoted key segment `"a"` is followed by `b"`; a `"` inside the segment must be escaped as `\\"`.'
+    with pytest.raises(Exception) as e:
+        loads_fn('"a" .b: 1')
+    assert str(e.value) == 'Line 1: Invalid key: "a" .b. Quoted key segment `"a"` is followed by whitespace; remove it, or move it inside the quotes.'
+
+def test_numeric_dict_keys_are_quoted(loads_fn, dumps_fn):
+    assert loads_fn('"2024".count: 5') == {"2024": {"count": 5}}
+    assert loads_fn('years."2024": 1\nyears."2025": 2') == {"years": {"2024": 1, "2025": 2}}
+    assert dumps_fn({"2024": {"count": 5}}) == '"2024".count: 5'
+    with pytest.raises(Exception, match=r'Line 1: Invalid key: 2024.count. A numeric dict key is quoted, `"2024"`; a list index is `\$2024`.') as exc:
+        loads_fn("2024.count: 5")
+    assert exc.value.kind == "invalid_key"
+
+def test_loads_into_fills_the_given_dict():
+    from stc import loads_into
+    target = {"stale": 1}
+    same = target
+    assert loads_into("a.b: 1\nc.$0: `true`", target) is None
+    assert target is same
+    assert target == {"a": {"b": 1}, "c": [True]}
+    loads_into("d: 2.5", target, duplicate_keys="last_wins")
+    assert target is same and target == {"d": 2.5}
+
+@pytest.mark.errors
+def test_loads_into_rejects_lists_and_keeps_the_dict_on_errors():
+    from stc import loads_into
+    target = {"kept": 1}
+    with pytest.raises(ValueError, match="top level is a dict, not a list"):
+        loads_into("$0: 1", target)
+    with pytest.raises(Exception, match="Line 2"):
+        loads_into("a: 1\na: 2", target)
+    assert target == {"kept": 1}
+    with pytest.raises(ValueError, match="`readonly` cannot be used with `loads_into`"):
+        loads_into("a: 1", target, readonly=True)
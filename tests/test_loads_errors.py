@@ -14,16 +14,233 @@ def test_number_with_backticks_is_invalid(loads_fn):
 
 def test_bool_wrong_spelling_is_invalid(loads_fn):
     doc = "a: `True`"  # spec requires lower-case true/false in backticks
-    with pytest.raises(Exception):
+    with pytest.raises(Exception, match="Line 1: Invalid value: `True`. Booleans are lower-case: `true`."):
         loads_fn(doc)
 
+def test_case_insensitive_literals(loads_fn):
+    doc = "a: `True`\nb: `FALSE`\nc: `true`"
+    assert loads_fn(doc, case_insensitive_literals=True) == {"a": True, "b": False, "c": True}
+    with pytest.raises(Exception, match="Booleans are lower-case: `false`"):
+        loads_fn("b: `FALSE`")
+    with pytest.raises(Exception, match="Invalid value: `Truth`"):
+        loads_fn("a: `Truth`", case_insensitive_literals=True)
+
 def test_malformed_key_path_is_invalid(loads_fn):
     for d in [
         "a..b: 1",     # empty segment
-        "a.$: 1",      # missing index
+        "a.$-: 1",     # missing index
         "a.$1b: 1",    # junk after index
         ".a: 1",       # leading dot
         "a.: 1",       # trailing dot before colon
     ]:
         with pytest.raises(Exception):
             loads_fn(d)
+
+def test_list_index_errors_name_the_bad_index(loads_fn):
+    with pytest.raises(Exception, match=r"Line 1: Invalid key: a\.\$abc\. List index must be numeric, found `abc`\.") as exc:
+        loads_fn("a.$abc: 1")
+    assert exc.value.kind == "invalid_key"
+    with pytest.raises(Exception, match=r"Line 1: Invalid key: a\.\$1b\. List index must be numeric, found `1b`\."):
+        loads_fn("a.$1b: 1")
+    with pytest.raises(Exception, match=r"Line 1: Invalid key: a\.\$-\. List index `\$-` is missing its number\."):
+        loads_fn("a.$-: 1")
+
+def test_inline_string_after_fence_is_invalid(loads_fn):
+    with pytest.raises(Exception, match=r"Line 1: Unexpected `hello```` after the opening fence"):
+        loads_fn("key: ```hello```")
+
+def test_trailing_text_after_fence_is_not_dropped(loads_fn):
+    with pytest.raises(Exception, match="after the opening fence"):
+        loads_fn("key: ```foo bar\nbaz\n```")
+
+def test_bare_word_suggests_string_block(loads_fn):
+    with pytest.raises(Exception, match="Did you mean a string block"):
+        loads_fn("key: hello")
+    with pytest.raises(Exception, match="Did you mean a string block"):
+        loads_fn("key: hello world")
+
+def test_bare_bool_suggests_backticks(loads_fn):
+    with pytest.raises(Exception, match="Booleans are written in backticks"):
+        loads_fn("key: true")
+
+def test_leading_zero_integer_is_rejected(loads_fn):
+    with pytest.raises(Exception, match="Line 1: Invalid value: 007. Integers cannot have leading zeros"):
+        loads_fn("key: 007")
+    with pytest.raises(Exception, match="leading zeros"):
+        loads_fn("key: -01")
+
+def test_leading_zero_is_rejected_when_scientific_ints_makes_an_integer(loads_fn):
+    with pytest.raises(Exception, match="Line 1: Invalid value: 01234e0. Integers cannot have leading zeros"):
+        loads_fn("zip: 01234e0", scientific_ints=True)
+    with pytest.raises(Exception, match="leading zeros"):
+        loads_fn("zip: -01.5e1", scientific_ints=True)
+    assert loads_fn("a: 0.5e1\nb: 0e0", scientific_ints=True) == {"a": 5, "b": 0}
+    # still a float without the option, as before
+    assert loads_fn("zip: 01234e0") == {"zip": 1234.0}
+
+def test_zero_is_a_valid_integer(loads_fn):
+    assert loads_fn("a: 0\nb: -0\nc: 0.5") == {"a": 0, "b": 0, "c": 0.5}
+
+def test_parse_error_exposes_line_and_kind(loads_fn):
+    with pytest.raises(Exception) as exc:
+        loads_fn("a: 1\nno colon here")
+    assert exc.value.lineno == 2
+    assert exc.value.colno is None
+    assert exc.value.kind == "missing_colon"
+
+def test_parse_error_kinds(loads_fn):
+    for doc, kind in [("a..b: 1", "invalid_key"), ("a: 1\na: 2", "duplicate_value"), ("a: ```\nx", "unclosed_string")]:
+        with pytest.raises(Exception) as exc:
+            loads_fn(doc)
+        assert exc.value.kind == kind
+
+def test_json_block_error_has_a_column(loads_fn):
+    with pytest.raises(Exception) as exc:
+        loads_fn("a: ```json\n{\"x\": }\n```", validate_block_content=True)
+    assert (exc.value.lineno, exc.value.colno) == (2, 7)
+
+def test_colon_inside_value_suggests_string_block(loads_fn):
+    with pytest.raises(Exception, match=r"Line 1: Invalid value: 12:30. Only the first `:` separates key and value"):
+        loads_fn("time: 12:30")
+
+def test_max_bytes_limit(loads_fn):
+    doc = "a: 1\nb: 2"  # 9 bytes
+    assert loads_fn(doc, max_bytes=9) == {"a": 1, "b": 2}
+    with pytest.raises(Exception, match="Line 2: Input exceeds max_bytes=8") as exc:
+        loads_fn(doc, max_bytes=8)
+    assert exc.value.kind == "limit_exceeded"
+
+def test_max_lines_limit(loads_fn):
+    doc = "a: 1\nb: 2\n"
+    assert loads_fn(doc, max_lines=2) == {"a": 1, "b": 2}
+    with pytest.raises(Exception, match="Line 2: Input exceeds max_lines=1"):
+        loads_fn(doc, max_lines=1)
+    with pytest.raises(Exception, match="Line 4: Input exceeds max_lines=2"):
+        loads_fn(doc + "\n", max_lines=2)
+
+def test_oversized_list_index_is_rejected_on_its_line(loads_fn):
+    with pytest.raises(Exception, match=r"Line 2: Invalid key: a\.\$999999999\. List index `\$999999999` is larger than the maximum") as exc:
+        loads_fn("b: 1\na.$999999999: 1")
+    assert exc.value.kind == "limit_exceeded"
+    with pytest.raises(Exception, match="is larger than the maximum"):
+        loads_fn("a.$-99999999999999999999999: 1")
+
+def test_max_list_len_limit(loads_fn):
+    doc = "x.a.$0: 1\nx.a.$1: 2\nx.a.$2: 3"
+    assert loads_fn(doc, max_list_len=3) == {"x": {"a": [1, 2, 3]}}
+    with pytest.raises(Exception, match="Line 1: List `x.a` has 3 items, more than max_list_len=2") as exc:
+        loads_fn(doc, max_list_len=2)
+    assert exc.value.kind == "limit_exceeded"
+    assert loads_fn("a: [1, 2]", max_list_len=2) == {"a": [1, 2]}
+    with pytest.raises(Exception, match=r"Line 1: Invalid value: \[1, 2, 3\]\. The inline list has 3 items, more than max_list_len=2") as exc:
+        loads_fn("a: [1, 2, 3]", max_list_len=2)
+    assert exc.value.kind == "limit_exceeded"
+
+def test_pretty_errors_put_a_caret_under_the_bad_key_segment(loads_fn):
+    doc = "a: 1\n  server.bad-name.port: 80"
+    with pytest.raises(Exception) as plain:
+        loads_fn(doc)
+    assert "\n" not in str(plain.value)
+    assert (plain.value.lineno, plain.value.colno) == (2, 10)
+    with pytest.raises(Exception) as exc:
+        loads_fn(doc, pretty_errors=True)
+    assert str(exc.value).endswith("\n".join([
+        "Key must be a valid identifier.",
+        "  |",
+        "2 |   server.bad-name.port: 80",
+        "  |          ^",
+    ]))
+
+def test_pretty_errors_point_at_the_value(loads_fn):
+    with pytest.raises(Exception) as exc:
+        loads_fn("key:   nope", pretty_errors=True)
+    assert exc.value.colno == 8
+    assert str(exc.value).endswith("1 | key:   nope\n  |        ^")
+
+@pytest.mark.parametrize("doc", ["key:", "key:   ", "a: 1\nkey:"])
+def test_missing_value_has_its_own_error(loads_fn, doc):
+    with pytest.raises(Exception, match=r"Missing value after `:`") as exc:
+        loads_fn(doc)
+    assert exc.value.kind == "missing_value"
+
+def test_empty_values_policy(loads_fn):
+    assert loads_fn("a:\nb.$0: \nc: 1", empty_values="null") == {"a": None, "b": [None], "c": 1}
+    assert loads_fn("a:", empty_values="empty_string") == {"a": ""}
+    with pytest.raises(ValueError, match="Unknown empty_values policy"):
+        loads_fn("a:", empty_values="none")
+
+@pytest.mark.parametrize("doc, lineno, message", [
+    ("a: 1\nb: ```\n```", 3, r"Line 3: Empty string block should be formatted as"),
+    ("a: 1\nb: ~~~\nx", 2, r"Line 2: Unclosed string block: the input ends at line 3 without its closing ~~~ fence\."),
+    ("a: 1\nb: ```hi```", 2, r"Line 2: Unexpected `hi```` after the opening fence"),
+])
+def test_string_block_errors_carry_a_line(loads_fn, doc, lineno, message):
+    with pytest.raises(Exception, match=message) as exc:
+        loads_fn(doc)
+    assert exc.value.lineno == lineno
+
+def test_separator_inside_string_block_carries_a_line():
+    from stc.stc_rust import loads_all
+    with pytest.raises(Exception, match=r"Line 3: Document separator `---` inside the string block starting at line 1\.") as exc:
+        loads_all("a: ```\nx\n---\n")
+    assert exc.value.lineno == 3
+
+def test_required_keys_present(loads_fn):
+    doc = "server.host: ```\nlocalhost\n```\nserver.port: 80\nitems.$0: 1"
+    assert loads_fn(doc, required=["server.host", "server.port", "items.$-1"])["server"]["port"] == 80
+
+def test_missing_required_keys_are_reported_together(loads_fn):
+    doc = "server.host: ```\nlocalhost\n```\nitems.$0: 1"
+    with pytest.raises(Exception, match=r"^Missing required keys: `server.port`, `items.\$1`\.$") as exc:
+        loads_fn(doc, required=["server.host", "server.port", "items.$1"])
+    assert exc.value.kind == "missing_required"
+    assert exc.value.lineno is None
+    with pytest.raises(Exception, match=r"^Missing required key: `server.host.x`\.$"):
+        loads_fn(doc, required=["server.host.x"])
+
+def test_invalid_value_escapes_invisible_characters(loads_fn):
+    with pytest.raises(Exception, match=r"^Line 1: Invalid value: 1\\u\{a0\}000\. ") as exc:
+        loads_fn("n: 1\u00a0000")
+    assert exc.value.kind == "invalid_value"
+    with pytest.raises(Exception, match=r"Invalid value: `true\\u\{200b\}`\."):
+        loads_fn("flag: `true\u200b`")
+    with pytest.raises(Exception, match=r"Invalid value: \[1,\\u\{9\}2\. The inline list has no closing"):
+        loads_fn("a: [1,\t2")
+
+def test_zero_width_space_in_key_names_the_codepoint(loads_fn):
+    with pytest.raises(Exception, match=r"^Line 2: Invalid key: server\.po\\u\{200b\}rt\. Key segment `po\\u\{200b\}rt` "
+                       r"contains U\+200B ZERO WIDTH SPACE, an invisible character; retype the key\.$") as exc:
+        loads_fn("a: 1\nserver.po\u200brt: 80")
+    assert (exc.value.kind, exc.value.lineno, exc.value.colno) == ("invalid_key", 2, 10)
+
+def test_byte_order_mark_is_only_allowed_at_the_start(loads_fn):
+    assert loads_fn("\ufeffa: 1\nb: 2") == {"a": 1, "b": 2}
+    with pytest.raises(Exception, match=r"Line 2: .*contains U\+FEFF BYTE ORDER MARK"):
+        loads_fn("a: 1\n\ufeffb: 2")
+    with pytest.raises(Exception, match=r"Key segment `ke\\u\{feff\}y` contains U\+FEFF BYTE ORDER MARK"):
+        loads_fn("ke\ufeffy: 1")
+
+def test_try_loads_returns_errors_as_values():
+    from stc import try_loads
+    assert try_loads("a.$0: 1\nb: `true`") == ({"a": [1], "b": True}, None)
+    assert try_loads("a: 1\na: 2") == (None, "Line 2: Key `a` is set at least two values \"Int(1)\" | \"Int(2)\".")
+    value, message = try_loads("a: 1\nno colon here")
+    assert value is None and message.startswith("Line 2: Missing `:`.")
+    assert try_loads("a: 1\na: 2", duplicate_keys="last_wins") == ({"a": 2}, None)
+    with pytest.raises(TypeError, match="unexpected keyword argument"):
+        try_loads("a: 1", dednet=True)
+
+def test_float_overflow_and_infinity_literals_have_distinct_errors(loads_fn):
+    with pytest.raises(Exception, match=r"^Line 2: Invalid value: 1e400\. The number is too large for a 64-bit float "
+                       r"and would become infinity; use a string block to keep the text\.$") as exc:
+        loads_fn("a: 1\nb: 1e400")
+    assert (exc.value.kind, exc.value.lineno, exc.value.colno) == ("invalid_value", 2, 4)
+    with pytest.raises(Exception, match=r"^Line 1: Invalid value: inf\. STC numbers cannot be infinity or NaN; "
+                       r"use a string block to keep the text\.$") as exc:
+        loads_fn("b: inf")
+    assert exc.value.lineno == 1
+    with pytest.raises(Exception, match=r"Invalid value: -1e400\. The number is too large"):
+        loads_fn("b: [1, -1e400]")
+    with pytest.raises(Exception, match=r"Invalid value: NaN\. STC numbers cannot be infinity or NaN"):
+        loads_fn("b: NaN")
+    assert loads_fn("b: 1e-400") == {"b": 0.0}
@@ -0,0 +1,101 @@
+import hashlib
+import io
+import json
+
+import pytest
+
+from stc.stc_rust import canonical_json, loads_json_str, to_json, to_jsonl
+
+pytestmark = pytest.mark.json
+
+DOC = "\n".join([
+    "a.b: 1",
+    "a.c.$0: 2.5",
+    "a.c.$1.d: `true`",
+    "name: ```\nhé \"quoted\"\n```",
+    "empty.l: []",
+    "empty.d: {}",
+])
+
+def test_loads_json_str_matches_json_dumps_of_loads(loads_fn):
+    expected = json.dumps(loads_fn(DOC), separators=(",", ":"), ensure_ascii=False)
+    assert loads_json_str(DOC) == expected
+    assert json.loads(loads_json_str(DOC)) == loads_fn(DOC)
+
+def test_loads_json_str_writes_base64_blocks_as_strings():
+    doc = "a: b64```\naGk=\n```"
+    assert loads_json_str(doc) == '{"a":"aGk="}'
+    assert loads_json_str(doc) == to_json(doc, indent=None)
+
+def test_loads_json_str_takes_the_parse_options_of_loads():
+    assert loads_json_str("a = 1.50\nb = +5", separator="=", preserve_number_lexemes=True) == '{"a":1.50,"b":5}'
+    assert loads_json_str("a = 1e3", separator="=", scientific_ints=True) == '{"a":1000}'
+    with pytest.raises(ValueError, match="`normalize` only applies to `loads`, not to `loads_json_str`"):
+        loads_json_str("a: 1", normalize=True)
+
+@pytest.mark.errors
+def test_loads_json_str_raises_parse_errors():
+    from stc.exceptions import STCParseError
+    with pytest.raises(STCParseError):
+        loads_json_str("a: nope")
+
+def test_to_json_indents_by_the_requested_width(loads_fn):
+    out = to_json("b.y: 1\na.$0: `true`", indent=4)
+    assert out == '{\n    "a": [\n        true\n    ],\n    "b": {\n        "y": 1\n    }\n}'
+    assert json.loads(out) == loads_fn("b.y: 1\na.$0: `true`")
+    assert to_json("a: 1").startswith('{\n  "a"')
+
+def test_to_json_zero_or_none_is_compact():
+    assert to_json(DOC, indent=0) == to_json(DOC, indent=None) == loads_json_str(DOC)
+
+def test_to_json_can_keep_float_text():
+    doc = "x: 1.50\ny: 1e2\nz: +2.5\nn: 7"
+    assert to_json(doc, indent=None) == '{"n":7,"x":1.5,"y":100.0,"z":2.5}'
+    assert to_json(doc, indent=None, keep_float_text=True) == '{"n":7,"x":1.50,"y":1e2,"z":2.5}'
+
+def test_to_json_takes_the_parse_options_of_loads():
+    assert to_json("a = 1\nb =", indent=None, separator="=", empty_values="null") == '{"a":1,"b":null}'
+    assert to_json("x: 1.50", indent=None, use_decimal=True) == '{"x":1.50}'
+    with pytest.raises(ValueError, match="`readonly` only applies to `loads`, not to `to_json`."):
+        to_json("a: 1", readonly=True)
+
+def test_to_json_writes_bytes_as_base64():
+    assert to_json("blob: b64```\naGVsbG8gd29ybGQ=\n```", indent=None) == '{"blob":"aGVsbG8gd29ybGQ="}'
+
+def test_to_jsonl_matches_the_items_of_the_full_parse(loads_fn):
+    doc = "\n".join([
+        "$0.name: ```\nfirst\n```",
+        "$0.tags: [1, 2]",
+        "$1.name: \"second\"",
+        "$2: 3.5",
+        "$1.nested.$0.ok: `true`",
+    ])
+    out = to_jsonl(doc)
+    assert out.endswith("\n") and out.count("\n") == 3
+    assert [json.loads(line) for line in out.splitlines()] == loads_fn(doc)
+    buf = io.StringIO()
+    assert to_jsonl(doc, buf) is None
+    assert buf.getvalue() == out
+    assert to_jsonl("[]") == ""
+
+def test_to_jsonl_takes_the_parse_options_of_loads():
+    assert to_jsonl("$0 = 1.50\n$1.x = 2", separator="=", use_decimal=True) == '1.50\n{"x":2}\n'
+    buf = io.StringIO()
+    to_jsonl("$0: 1.5e1", buf, float_to_int_when_whole=True)
+    assert buf.getvalue() == "15\n"
+
+@pytest.mark.errors
+def test_to_jsonl_needs_a_top_level_list():
+    with pytest.raises(ValueError, match="The top-level value must be a list"):
+        to_jsonl("a: 1")
+    with pytest.raises(Exception, match="List `<root>` is missing index 1"):
+        to_jsonl("$0: 1\n$2: 3")
+
+def test_canonical_json_ignores_line_order_and_whitespace():
+    one = "\n".join(["server.port: 80", "server.host: ```\nlocalhost\n```", "ratio: 0.50", "tags.$0: \"a\"", "z: -0.0"])
+    two = "\n".join(["z: 0.0", "  ratio: 0.5  ", "tags.$0: \"a\"", "", "server.host: ```\nlocalhost\n```", "server.port: 80"])
+    a, b = canonical_json(one), canonical_json(two)
+    assert a == b == '{"ratio":0.5,"server":{"host":"localhost","port":80},"tags":["a"],"z":0.0}'
+    assert hashlib.sha256(a.encode()).hexdigest() == hashlib.sha256(b.encode()).hexdigest()
+    assert canonical_json("a: 1") != canonical_json("a: 1.0")
+    assert canonical_json("a: b64```\naGk=\n```") == '{"a":"aGk="}'
@@ -0,0 +1,41 @@
+import pytest
+
+from stc.stc_rust import dumps, loads
+
+pytestmark = pytest.mark.lexemes
+
+@pytest.mark.parametrize("lexeme, value", [("1.50", 1.5), ("+5", 5), ("1e3", 1000.0)])
+def test_number_lexemes_round_trip_byte_identically(lexeme, value):
+    src = f"a.x: {lexeme}"
+    out = loads(src, preserve_number_lexemes=True)
+    assert out == {"a": {"x": value}}
+    assert out["a"]["x"].lexeme == lexeme
+    assert dumps(out) == src
+
+def test_lexemes_are_dropped_without_the_flag():
+    out = loads("x: 1.50\ny: +5", preserve_number_lexemes=False)
+    assert type(out["x"]) is float and type(out["y"]) is int
+    assert dumps(out) == "x: 1.5\ny: 5"
+
+def test_canonical_numbers_stay_plain():
+    out = loads("x: 1.5\ny: 5", preserve_number_lexemes=True)
+    assert type(out["x"]) is float and type(out["y"]) is int
+
+def test_stale_lexeme_is_not_written():
+    out = loads("x: 1.50", preserve_number_lexemes=True)
+    out["x"].lexeme = "2.0"
+    assert dumps(out) == "x: 1.5"
+
+def test_lexemes_keep_the_scientific_ints_typing():
+    out = loads("a: 1e3\nb: 1.5e1", preserve_number_lexemes=True, scientific_ints=True)
+    assert type(out["a"]).__name__ == "LexemeInt" and out["a"] == 1000 and out["a"].lexeme == "1e3"
+    assert type(out["b"]).__name__ == "LexemeInt" and out["b"] == 15
+    assert dumps(out) == "a: 1e3\nb: 1.5e1"
+    assert type(loads("a: 1e3", preserve_number_lexemes=True)["a"]).__name__ == "LexemeFloat"
+
+def test_lexemes_keep_the_float_to_int_when_whole_typing():
+    out = loads("a: 2.0\nb: 2.50", float_to_int_when_whole=True, preserve_number_lexemes=True)
+    assert type(out["a"]).__name__ == "LexemeInt" and out["a"] == 2 and out["a"].lexeme == "2.0"
+    assert type(out["b"]).__name__ == "LexemeFloat" and out["b"] == 2.5
+    assert dumps(out) == "a: 2.0\nb: 2.50"
+    assert type(loads("a: 2.0", float_to_int_when_whole=True, use_decimal=True)["a"]) is int
@@ -29,8 +29,8 @@ def test_list_item_can_be_string_block(loads_fn):
     assert loads_fn(doc) == {"a": ["hello"]}
 
 @pytest.mark.errors
-def test_list_index_must_be_non_negative_integer(loads_fn):
-    for bad in ["a.$-1: 1", "a.$x: 1", "a.$1.2: 1"]:
+def test_list_index_must_be_an_integer_in_range(loads_fn):
+    for bad in ["a.$-2: 1", "a.$x: 1", "a.$1.2: 1", "a.$-: 1"]:
         with pytest.raises(Exception):
             loads_fn(bad)
 
@@ -41,3 +41,122 @@ def test_list_gaps_or_duplicates_should_error(loads_fn):
         loads_fn("a.$1: 1")  # gap at $0
     with pytest.raises(Exception):
         loads_fn("a.$0: 1\na.$0: 2")  # duplicate index
+
+def test_require_ordered_indices_accepts_ascending(loads_fn):
+    doc = "\n".join([
+        "a.$0.x: 1",
+        "a.$0.y: 2",
+        "a.$1.x: 3",
+        "b.$0: 4",
+    ])
+    assert loads_fn(doc, require_ordered_indices=True) == {
+        "a": [{"x": 1, "y": 2}, {"x": 3}],
+        "b": [4],
+    }
+
+@pytest.mark.errors
+def test_require_ordered_indices_rejects_descending(loads_fn):
+    doc = "a.$1: 1\na.$0: 2"
+    assert loads_fn(doc) == {"a": [2, 1]}
+    with pytest.raises(Exception, match=r"List `a` has index \$1 as its first index"):
+        loads_fn(doc, require_ordered_indices=True)
+
+@pytest.mark.errors
+def test_require_ordered_indices_rejects_out_of_order(loads_fn):
+    doc = "a.$0: 1\na.$2: 3\na.$1: 2"
+    assert loads_fn(doc) == {"a": [1, 2, 3]}
+    with pytest.raises(Exception, match=r"Line 2: List `a` has index \$2 after \$0"):
+        loads_fn(doc, require_ordered_indices=True)
+
+def test_negative_index_counts_from_the_end(loads_fn):
+    doc = "\n".join([
+        "a.$-1: ```\nlast\n```",
+        "a.$0: 1",
+        "a.$1: 2",
+    ])
+    assert loads_fn(doc) == {"a": [1, 2, "last"]}
+    assert loads_fn("a.$-1.x: 1\na.$-2.x: 0") == {"a": [{"x": 0}, {"x": 1}]}
+
+@pytest.mark.errors
+def test_negative_index_colliding_with_explicit_index(loads_fn):
+    # three entries, so `$-1` is `$2`
+    doc = "a.$0: 1\na.$2: 2\na.$-1: 3"
+    with pytest.raises(Exception, match=r"sets index \$2 twice"):
+        loads_fn(doc)
+
+def test_missing_index_points_at_the_line_that_declared_the_list(loads_fn):
+    doc = "\n".join(["name: 1", "", "", "servers.$0.port: 80", "servers.$1.port: 81", "servers.$3.port: 83"])
+    with pytest.raises(Exception, match=r"Line 4: List `servers` is missing index 2"):
+        loads_fn(doc)
+
+def test_list_and_dict_conflict_reports_a_line(loads_fn):
+    with pytest.raises(Exception, match=r"Line 2: a is set both as a list and a dict"):
+        loads_fn("x: 1\na.$0: 1\na.b: 2")
+
+def test_auto_index_assigns_the_next_index_in_source_order(loads_fn):
+    doc = "\n".join([
+        'hosts.$: "a"',
+        "port: 80",
+        'hosts.$: "b"',
+        "hosts.$: ```\nc\n```",
+    ])
+    assert loads_fn(doc) == {"hosts": ["a", "b", "c"], "port": 80}
+    assert loads_fn("m.$.$: 1\nm.$.$: 2") == {"m": [[1], [2]]}
+
+@pytest.mark.errors
+def test_auto_and_explicit_indices_cannot_mix(loads_fn):
+    with pytest.raises(Exception, match=r"Line 2: List `a` mixes auto-assigned `\$` and explicit `\$N` indices"):
+        loads_fn("a.$: 1\na.$1: 2")
+    with pytest.raises(Exception, match=r"Line 2: List `a` mixes"):
+        loads_fn("a.$0: 1\na.$: 2")
+
+@pytest.mark.errors
+def test_repeated_list_index_names_both_lines(loads_fn):
+    doc = "\n".join([
+        "items.$0: 1",
+        "items.$1: 2",
+        "other: 3",
+        "items.$2: 4",
+        "items.$1: ```\nagain\n```",
+    ])
+    with pytest.raises(Exception, match=r"Line 5: List `items` sets index \$1 twice, on line 2 and line 5\.") as exc:
+        loads_fn(doc)
+    assert exc.value.kind == "duplicate_value"
+    assert loads_fn(doc, duplicate_keys="last_wins") == {"items": [1, "again", 4], "other": 3}
+
+@pytest.mark.errors
+def test_list_and_dict_conflict_names_both_lines(loads_fn):
+    doc = "\n".join(["x: 1", "items.name: ```\nfoo\n```", "y: 2", "items.$0: 1"])
+    with pytest.raises(Exception, match=r"items is set both as a list and a dict: first `\$N` index on line 6, first named key on line 2\."):
+        loads_fn(doc)
+
+def test_inline_scalar_list(loads_fn):
+    doc = 'items: [1, -2.5, "a, b", `true`]\nempty: [ ]\nx.$0: 1'
+    assert loads_fn(doc) == {"items": [1, -2.5, "a, b", True], "empty": [], "x": [1]}
+
+@pytest.mark.parametrize("doc", ["items: [1, 2]\nitems.$2: 3", "items.$0: 1\nitems: [2]", "items: [1]\nitems.a: 1"])
+def test_inline_list_and_index_lines_conflict(loads_fn, doc):
+    with pytest.raises(Exception, match=r"Line 2: Key `items` is set both as an inline list") as exc:
+        loads_fn(doc)
+    assert exc.value.kind == "key_conflict"
+
+def test_inline_list_rejects_nesting_and_empty_items(loads_fn):
+    with pytest.raises(Exception, match="Inline lists cannot be nested"):
+        loads_fn("items: [1, [2, 3]]")
+    with pytest.raises(Exception, match="Inline lists cannot have empty items"):
+        loads_fn("items: [1, , 2]")
+    with pytest.raises(Exception, match="String blocks cannot be inline list items"):
+        loads_fn("items: [```]")
+
+def test_inline_list_allows_one_trailing_comma(loads_fn):
+    assert loads_fn("items: [1, 2, 3,]\nb: [ 1 , ]") == {"items": [1, 2, 3], "b": [1]}
+    with pytest.raises(Exception, match="Inline lists cannot have empty items"):
+        loads_fn("items: [1, 2,,]")
+    with pytest.raises(Exception, match="Inline lists cannot have empty items"):
+        loads_fn("items: [,]")
+
+def test_inline_list_comments(loads_fn):
+    doc = 'items: [1, "a # b"] # the ports\ntags: ["#x",] #'
+    assert loads_fn(doc) == {"items": [1, "a # b"], "tags": ["#x"]}
+    with pytest.raises(Exception, match=r"Line 1: Invalid value: \[1, # note. The inline list has no closing `\]`"):
+        loads_fn("items: [1, # note\n2]")
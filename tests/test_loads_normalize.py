@@ -0,0 +1,31 @@
+import pytest
+
+pytestmark = pytest.mark.normalize
+
+def test_normalized_results_compare_equal_across_source_order(loads_fn):
+    one = "\n".join(["b.y: 1", "b.x: 0.30000000000000004", "a.$1: 2", "a.$0: 1"])
+    two = "\n".join(["a.$0: 1", "a.$1: 2", "b.x: 0.3", "b.y: 1"])
+    n1 = loads_fn(one, normalize=True, float_precision=6)
+    n2 = loads_fn(two, normalize=True, float_precision=6)
+    assert n1 == n2
+    assert list(n1) == ["a", "b"]
+    assert n1 == {"a": (1, 2), "b": {"x": 0.3, "y": 1}}
+
+def test_normalized_lists_are_tuples(loads_fn):
+    out = loads_fn("a.$0.$0: 1", normalize=True)
+    assert out == {"a": ((1,),)}
+    # tuples are hashable, so normalized lists can be put in sets
+    assert {out["a"]} == {((1,),)}
+
+def test_float_precision_needs_normalize(loads_fn):
+    with pytest.raises(ValueError, match="only applies with `normalize=True`"):
+        loads_fn("x: 0.123456789", float_precision=2)
+    assert loads_fn("x: 0.123456789", normalize=True, float_precision=2) == {"x": 0.12}
+
+def test_float_precision_rounding_cannot_overflow(loads_fn):
+    assert loads_fn("a: 1e300", normalize=True, float_precision=17) == {"a": 1e300}
+    assert loads_fn("a: 1.5", normalize=True, float_precision=0) == {"a": 2.0}
+    with pytest.raises(ValueError, match="between 0 and 17, not 400"):
+        loads_fn("a: 1.5", normalize=True, float_precision=400)
+    with pytest.raises(ValueError, match="between 0 and 17"):
+        loads_fn("a: 1.5", normalize=True, float_precision=-1)
@@ -49,4 +49,119 @@ def test_empty_string_requires_blank_line_between_fences(loads_fn):
     # "key: ```\n\n```" is required; "key: ```\n```" is invalid
     doc = "a: ```\n```"
     with pytest.raises(Exception):
-        loads_fn(doc)
\ No newline at end of file
+        loads_fn(doc)
+
+def test_tilde_fence_encloses_literal_backticks(loads_fn):
+    content = "```python\nprint('hi')\n```\n````"
+    doc = f"a: ~~~\n{content}\n~~~"
+    assert loads_fn(doc) == {"a": content}
+
+def test_tilde_fence_is_not_closed_by_backticks(loads_fn):
+    doc = "a: ~~~~\nx\n```\n~~~\n~~~~"
+    assert loads_fn(doc) == {"a": "x\n```\n~~~"}
+
+def test_keep_trailing_newline_preserves_final_blank_line(loads_fn):
+    doc = "a: ```\nx\n\n```"
+    assert loads_fn(doc) == {"a": "x\n"}
+    assert loads_fn(doc, keep_trailing_newline=True) == {"a": "x\n\n"}
+
+def test_keep_trailing_newline_keeps_newline_of_last_line(loads_fn):
+    doc = "a: ```\nx\n```"
+    assert loads_fn(doc, keep_trailing_newline=True) == {"a": "x\n"}
+
+def test_keep_trailing_newline_allows_empty_block(loads_fn):
+    assert loads_fn("a: ```\n```", keep_trailing_newline=True) == {"a": ""}
+
+def test_dedent_strips_common_indentation(loads_fn):
+    doc = "\n".join([
+        "job.script: ```",
+        "    for x in xs:",
+        "        print(x)",
+        "    done()",
+        "```",
+    ])
+    assert loads_fn(doc) == {"job": {"script": "    for x in xs:\n        print(x)\n    done()"}}
+    assert loads_fn(doc, dedent=True) == {"job": {"script": "for x in xs:\n    print(x)\ndone()"}}
+
+def test_dedent_ignores_blank_lines_for_margin(loads_fn):
+    doc = "a: ```\n  x\n\n  y\n```"
+    assert loads_fn(doc, dedent=True) == {"a": "x\n\ny"}
+
+def test_fence_may_declare_a_language(loads_fn):
+    doc = "query: ```sql\nSELECT 1\n```"
+    assert loads_fn(doc) == {"query": "SELECT 1"}
+    assert loads_fn(doc, validate_block_content=True) == {"query": "SELECT 1"}
+
+def test_valid_json_block_passes_validation(loads_fn):
+    doc = 'payload: ```json\n{"a": [1, 2]}\n```'
+    assert loads_fn(doc, validate_block_content=True) == {"payload": '{"a": [1, 2]}'}
+
+@pytest.mark.errors
+def test_invalid_json_block_reports_line_inside_block(loads_fn):
+    doc = 'x: 1\npayload: ```json\n{"a": 1,\n "b": }\n```'
+    assert loads_fn(doc)["payload"] == '{"a": 1,\n "b": }'
+    with pytest.raises(Exception, match=r"Line 4: String block tagged `json` is not valid JSON \(block line 2"):
+        loads_fn(doc, validate_block_content=True)
+
+@pytest.mark.parametrize("src, value", [
+    (r'"hello\nworld"', "hello\nworld"),
+    (r'"a\tb"', "a\tb"),
+    (r'"back\\slash"', "back\\slash"),
+    (r'"say \"hi\""', 'say "hi"'),
+    (r'"caf\u00e9"', "caf\u00e9"),
+    ('"42"', "42"),
+    ('""', ""),
+])
+def test_inline_quoted_string_escapes(loads_fn, src, value):
+    assert loads_fn(f"key: {src}") == {"key": value}
+
+def test_inline_quoted_string_invalid_escape(loads_fn):
+    with pytest.raises(Exception, match=r"Line 1: Invalid escape `\\q` in quoted string"):
+        loads_fn(r'key: "a\qb"')
+    with pytest.raises(Exception, match="expected four hex digits"):
+        loads_fn(r'key: "\u12"')
+    with pytest.raises(Exception, match="must be escaped"):
+        loads_fn('key: "a"b"')
+
+def test_trailing_whitespace_in_blocks_is_kept_by_default(loads_fn):
+    doc = "a: ```\nx  \n   \ny\t\n```"
+    assert loads_fn(doc) == {"a": "x  \n   \ny\t"}
+    assert loads_fn(doc, strip_trailing_whitespace_in_blocks=True) == {"a": "x\n\ny"}
+
+def test_raw_block_keeps_final_newline(loads_fn):
+    assert loads_fn("a: r```\nx\n```") == {"a": "x\n"}
+    assert loads_fn("a: r~~~\nx  \n\n~~~", dedent=True, strip_trailing_whitespace_in_blocks=True) == {"a": "x  \n\n"}
+
+def test_raw_block_may_be_empty(loads_fn):
+    assert loads_fn("a: r```\n```\nb: 1") == {"a": "", "b": 1}
+
+def test_raw_block_with_language_tag(loads_fn):
+    assert loads_fn('a: r```json\n{"k": 1}\n```', validate_block_content=True) == {"a": '{"k": 1}\n'}
+
+@pytest.mark.errors
+def test_raw_marker_needs_a_full_fence(loads_fn):
+    with pytest.raises(Exception):
+        loads_fn("a: r``\nx\n``")
+
+def test_closing_fence_may_have_trailing_whitespace(loads_fn):
+    assert loads_fn("a: ```\nx\n```  \r\nb: 1") == {"a": "x", "b": 1}
+
+def test_indented_fence_is_content_by_default(loads_fn):
+    doc = "a: ```\nx\n  ```\ny\n```"
+    assert loads_fn(doc) == {"a": "x\n  ```\ny"}
+
+def test_allow_indented_fence_closes_on_an_indented_fence(loads_fn):
+    doc = "a: ```\n  x\n  ```\nb: 1"
+    assert loads_fn(doc, allow_indented_fence=True, dedent=True) == {"a": "x", "b": 1}
+    with pytest.raises(Exception, match="Unclosed string block"):
+        loads_fn(doc)
+
+def test_base64_block_gives_bytes(loads_fn):
+    doc = "blob: b64```\naGVsbG8g\nd29ybGQ=\n```\nempty: b64~~~\n~~~"
+    assert loads_fn(doc) == {"blob": b"hello world", "empty": b""}
+
+def test_invalid_base64_block_names_its_start(loads_fn):
+    with pytest.raises(Exception, match="Line 2: Invalid base64 in the block starting at line 2."):
+        loads_fn("a: 1\nblob: b64```\nab=c\n```")
+    with pytest.raises(Exception, match="annotated `str` but its value is a base64 block"):
+        loads_fn("blob(str): b64```\nAA==\n```")
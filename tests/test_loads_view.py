@@ -0,0 +1,23 @@
+import pytest
+
+pytestmark = pytest.mark.view
+
+def test_readonly_view_reads_like_a_dict(loads_fn):
+    doc = "\n".join([
+        "server.host: ```\nlocalhost\n```",
+        "server.port: 8080",
+    ])
+    cfg = loads_fn(doc, readonly=True)
+    assert cfg["server"]["port"] == 8080
+    assert cfg == {"server": {"host": "localhost", "port": 8080}}
+    assert "server" in cfg and len(cfg) == 1
+
+def test_readonly_view_suggests_closest_key(loads_fn):
+    cfg = loads_fn("server.port: 8080", readonly=True)
+    with pytest.raises(KeyError, match="did you mean `port`"):
+        cfg["server"]["prot"]
+
+def test_readonly_view_rejects_assignment(loads_fn):
+    cfg = loads_fn("a: 1", readonly=True)
+    with pytest.raises(TypeError):
+        cfg["a"] = 2
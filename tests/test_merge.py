@@ -0,0 +1,24 @@
+import pytest
+
+from stc import merge
+
+pytestmark = pytest.mark.query
+
+def test_merge_nested_dicts(loads_fn):
+    base = loads_fn("server.host: ```\nlocalhost\n```\nserver.port: 80\ndebug: `false`")
+    overlay = loads_fn("server.port: 8080\nserver.tls: `true`")
+    assert merge(base, overlay) == {
+        "server": {"host": "localhost", "port": 8080, "tls": True},
+        "debug": False,
+    }
+    # inputs are left untouched
+    assert base["server"]["port"] == 80
+
+def test_merge_replaces_arrays_by_default(loads_fn):
+    base = loads_fn("a.$0: 1\na.$1: 2")
+    overlay = loads_fn("a.$0: 3")
+    assert merge(base, overlay) == {"a": [3]}
+    assert merge(base, overlay, concat_arrays=True) == {"a": [1, 2, 3]}
+
+def test_merge_overlay_scalar_replaces_dict():
+    assert merge({"a": {"b": 1}}, {"a": 2}) == {"a": 2}
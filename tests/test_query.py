@@ -0,0 +1,110 @@
+import pytest
+
+from stc import empty_type_hints, iter_items, parse_with_spans, set_at, values_of_type
+
+pytestmark = pytest.mark.query
+
+DOC = "\n".join([
+    "title: ```\nHello\n```",
+    "items.$0.label: ```\nFirst\n```",
+    "items.$0.count: 3",
+    "items.$1.label: ```\nSecond\n```",
+    "items.$1.count: 5",
+    "ratio: 0.5",
+    "enabled: `true`",
+])
+
+def test_values_of_type_collects_strings():
+    assert values_of_type(DOC, "string") == [
+        ("items.$0.label", "First"),
+        ("items.$1.label", "Second"),
+        ("title", "Hello"),
+    ]
+
+def test_values_of_type_collects_ints():
+    assert values_of_type(DOC, "int") == [("items.$0.count", 3), ("items.$1.count", 5)]
+    assert values_of_type(DOC, "float") == [("ratio", 0.5)]
+    assert values_of_type(DOC, "bool") == [("enabled", True)]
+
+def test_values_of_type_sorts_by_key_not_by_line():
+    assert values_of_type("b: 1\na: 2\nl.$1: 4\nl.$0: 3", "int") == [("a", 2), ("b", 1), ("l.$0", 3), ("l.$1", 4)]
+
+@pytest.mark.errors
+def test_values_of_type_rejects_unknown_kind():
+    with pytest.raises(ValueError):
+        values_of_type(DOC, "str")
+
+def test_iter_items_yields_leaf_paths(loads_fn):
+    doc = "a.b: 1\na.c.$0: 2.5\na.c.$1.d: `true`\ne: ```\nhi\n```"
+    assert iter_items(loads_fn(doc)) == [
+        ("a.b", 1),
+        ("a.c.$0", 2.5),
+        ("a.c.$1.d", True),
+        ("e", "hi"),
+    ]
+
+def test_iter_items_keeps_empty_collections_and_top_level_lists():
+    assert iter_items({"a": {}, "b": []}) == [("a", {}), ("b", [])]
+    assert iter_items([{"x": 1}, [2]]) == [("$0.x", 1), ("$1.$0", 2)]
+
+def test_iter_items_lets_configs_be_diffed_by_path(loads_fn):
+    old = dict(iter_items(loads_fn("s.$0.port: 80\ns.$1.port: 81")))
+    new = dict(iter_items(loads_fn("s.$0.port: 80\ns.$1.port: 82")))
+    assert {p for p in old if old[p] != new.get(p)} == {"s.$1.port"}
+
+def test_set_at_sets_nested_scalars_in_place(loads_fn):
+    cfg = loads_fn("a.b.$0: 1\na.b.$1: 2\nname: ```\nx\n```")
+    set_at(cfg, "a.b.$1", 5)
+    set_at(cfg, "a.b.$-1", 6)
+    set_at(cfg, "a.b.$", 7)
+    set_at(cfg, "a.c.$0.d", "new")
+    set_at(cfg, "name", None)
+    assert cfg == {"a": {"b": [1, 6, 7], "c": [{"d": "new"}]}, "name": None}
+
+def test_set_at_rejects_conflicting_paths(loads_fn):
+    from stc.exceptions import STCParseError
+    cfg = loads_fn("a.b: 1\nl.$0: 1")
+    with pytest.raises(STCParseError, match=r"Key `a` is of type dict, not a list, so it cannot be indexed with `\$2`") as exc:
+        set_at(cfg, "a.$2", 1)
+    assert exc.value.kind == "key_conflict"
+    with pytest.raises(STCParseError, match=r"Key `a.b` is of type int, not a dict"):
+        set_at(cfg, "a.b.c", 1)
+    with pytest.raises(STCParseError, match=r"List `l` has 1 items, so `\$3` is out of range"):
+        set_at(cfg, "l.$3", 1)
+    with pytest.raises(STCParseError, match="Invalid key"):
+        set_at(cfg, "a..b", 1)
+    assert cfg == {"a": {"b": 1}, "l": [1]}
+
+def test_typed_empties_parse_and_carry_their_hint(loads_fn):
+    doc = "ports: []int\nlabels: {}str\nitems.$.tags: []\nitems.$.tags: []float\nplain: {}"
+    assert loads_fn(doc) == {"ports": [], "labels": {}, "items": [{"tags": []}, {"tags": []}], "plain": {}}
+    assert empty_type_hints(doc) == [("ports", "int"), ("labels", "str"), ("items.$1.tags", "float")]
+
+def test_typed_empty_hints_follow_negative_indices_and_duplicates(loads_fn):
+    assert empty_type_hints("a.$0: 1\na.$-1.x: []bool") == [("a.$1.x", "bool")]
+    assert empty_type_hints("a: []int\na: [1]", duplicate_keys="last_wins") == []
+    assert empty_type_hints("a: []int\na: []str", duplicate_keys="first_wins") == [("a", "int")]
+
+@pytest.mark.errors
+def test_typed_empty_with_unknown_type_is_rejected(loads_fn):
+    with pytest.raises(Exception, match=r"Line 1: Invalid value: \[\]uint. Unknown element type `uint`") as exc:
+        loads_fn("a: []uint")
+    assert (exc.value.kind, exc.value.colno) == ("invalid_value", 4)
+
+def test_spans_point_back_at_the_source():
+    doc = "server.port: 80\nitems.$1.name: ```\nsecond\n```\nitems.$0.name: `true`\n  \"a/b\".c: [1, 2]"
+    value, spans = parse_with_spans(doc)
+    assert value["items"][1]["name"] == "second"
+    assert spans == {
+        "/server/port": (1, 14, 1, 16),
+        "/items/1/name": (2, 16, 4, 4),
+        "/items/0/name": (5, 16, 5, 22),
+        "/a~1b/c": (6, 12, 6, 18),
+    }
+
+def test_spans_follow_negative_indices_and_duplicates():
+    _, spans = parse_with_spans("a.$0: 1\na.$-1: 2")
+    assert spans == {"/a/0": (1, 7, 1, 8), "/a/1": (2, 8, 2, 9)}
+    assert parse_with_spans("a: 1\na: 22", duplicate_keys="last_wins")[1] == {"/a": (2, 4, 2, 6)}
+    assert parse_with_spans("a: 1\na: 22", duplicate_keys="first_wins")[1] == {"/a": (1, 4, 1, 5)}
+    assert parse_with_spans("a: {}\na.b: 1", open_empty_collections=True)[1] == {"/a/b": (2, 6, 2, 7)}
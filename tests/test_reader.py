@@ -0,0 +1,71 @@
+import pytest
+
+pytestmark = pytest.mark.reader
+
+DOC = "\n".join([
+    "name: ```",
+    "demo",
+    "```",
+    "servers.$0.port: 80",
+    "servers.$1.port: 443",
+    "debug: `false`",
+    "",
+])
+
+def test_reader_yields_top_level_pairs(tmp_path):
+    from stc import StcReader
+    path = tmp_path / "cfg.stc"
+    path.write_text(DOC)
+    with StcReader(path) as r:
+        pairs = list(r)
+    assert pairs == [
+        ("name", "demo"),
+        ("servers", [{"port": 80}, {"port": 443}]),
+        ("debug", False),
+    ]
+
+def test_reader_yields_an_entry_before_the_rest_of_the_file_is_read(tmp_path):
+    from stc import StcReader
+    from stc.exceptions import STCParseError
+    path = tmp_path / "big.stc"
+    path.write_text("a.x: 1\na.y: 2\nb: 3\nc: not a value\n" + "d: 4\n" * 1000)
+    with StcReader(path) as r:
+        assert next(r) == ("a", {"x": 1, "y": 2})
+        # the bad line is only reached by the next entry
+        with pytest.raises(STCParseError, match="Line 4"):
+            next(r)
+
+def test_reader_matches_loads(tmp_path, loads_fn):
+    from stc import StcReader
+    path = tmp_path / "cfg.stc"
+    path.write_text(DOC)
+    with StcReader(str(path)) as r:
+        assert dict(r) == loads_fn(DOC)
+
+def test_reader_yields_list_items_by_index(tmp_path):
+    from stc import StcReader
+    path = tmp_path / "list.stc"
+    path.write_text("".join(f"${i}: {i}\n" for i in range(12)))
+    with StcReader(path) as r:
+        pairs = list(r)
+    assert pairs == [(f"${i}", i) for i in range(12)]
+
+@pytest.mark.errors
+def test_reader_rejects_a_top_level_key_split_by_other_keys(tmp_path):
+    from stc import StcReader
+    from stc.exceptions import STCParseError
+    path = tmp_path / "split.stc"
+    path.write_text("a.x: 1\nb: 2\na.y: 3\n")
+    with pytest.raises(STCParseError, match=r"Line 3: `a` is written again after other top-level keys"):
+        with StcReader(path) as r:
+            list(r)
+
+@pytest.mark.errors
+def test_reader_raises_parse_errors(tmp_path):
+    from stc import StcReader
+    from stc.exceptions import STCParseError
+    path = tmp_path / "bad.stc"
+    path.write_text("a: ```\nunclosed")
+    with pytest.raises(STCParseError):
+        with StcReader(path) as r:
+            list(r)
@@ -0,0 +1,63 @@
+import pytest
+
+from stc.stc_rust import to_toml
+
+pytestmark = pytest.mark.toml
+
+def test_tables_and_scalars():
+    doc = "\n".join([
+        'name: "demo"',
+        "server.port: 8080",
+        "server.ratio: 0.5",
+        'server.tags.$0: "a"',
+        'server.tags.$1: "b"',
+        "server.tls.enabled: `true`",
+    ])
+    assert to_toml(doc) == "\n".join([
+        'name = "demo"',
+        "",
+        "[server]",
+        "port = 8080",
+        "ratio = 0.5",
+        'tags = ["a", "b"]',
+        "",
+        "[server.tls]",
+        "enabled = true",
+        "",
+    ])
+
+def test_list_of_dicts_becomes_array_of_tables():
+    doc = "jobs.$0.name: \"build\"\njobs.$0.env.CI: 1\njobs.$1.name: \"test\""
+    assert to_toml(doc) == "\n".join([
+        "[[jobs]]",
+        'name = "build"',
+        "",
+        "[jobs.env]",
+        "CI = 1",
+        "",
+        "[[jobs]]",
+        'name = "test"',
+        "",
+    ])
+
+def test_strings_are_escaped():
+    assert to_toml('msg: "tab\\there"') == 'msg = "tab\\there"\n'
+    assert to_toml('msg: "say \\"hi\\""') == 'msg = "say \\"hi\\""\n'
+
+def test_nested_lists_stay_inline():
+    assert to_toml("m.$0.$0: 1\nm.$1.$0: 2") == "m = [[1], [2]]\n"
+
+@pytest.mark.errors
+def test_null_is_rejected():
+    with pytest.raises(ValueError, match=r"Key `b.c` is null, which TOML cannot represent."):
+        to_toml("a: 1\nb.c:", empty_values="null")
+
+def test_parse_options_apply():
+    assert to_toml("a = 1\nb =", separator="=", empty_values="empty_string") == 'a = 1\nb = ""\n'
+
+@pytest.mark.errors
+def test_heterogeneous_array_is_rejected():
+    with pytest.raises(ValueError, match=r"Array `a` mixes int and string values"):
+        to_toml("a.$0: 1\na.$1: \"x\"")
+    with pytest.raises(ValueError, match=r"Array `a` mixes table and int values"):
+        to_toml("a.$0.b: 1\na.$1: 2")
@@ -0,0 +1,19 @@
+//! Run with `wasm-pack test --node -- --no-default-features --features wasm`.
+#![cfg(all(feature = "wasm", target_arch = "wasm32"))]
+
+use stc_rust::parse_to_js;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[wasm_bindgen_test]
+fn nested_document_becomes_plain_js_objects() {
+    let doc = "server.ports.$0: 80\nserver.ports.$1: 443\nserver.tls: `true`\nkey: b64```\naGk=\n```\nname: ```\napi\n```";
+    let js = parse_to_js(doc).unwrap();
+    let json = String::from(js_sys::JSON::stringify(&js).unwrap());
+    assert_eq!(json, r#"{"key":"aGk=","name":"api","server":{"ports":[80,443],"tls":true}}"#);
+}
+
+#[wasm_bindgen_test]
+fn errors_are_thrown_as_their_message() {
+    let err = parse_to_js("a: 1\nb: hello").unwrap_err();
+    assert!(err.as_string().unwrap().starts_with("Line 2: Invalid value: hello."));
+}